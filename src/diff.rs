@@ -0,0 +1,120 @@
+//! Line-by-line comparison of two small text files, shown in the app when no external
+//! diff tool is installed.
+
+use std::{fs, path::Path};
+
+use crate::fs_ops::{display_path, long_path};
+
+// Files past either limit are better left to a real diff tool; the comparison below
+// needs memory for every pair of lines
+const MAX_BYTES: u64 = 256 * 1024;
+const MAX_LINES: usize = 2000;
+
+/// One line of the comparison.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Line {
+    Same(String),
+    Removed(String), // Only in the first file
+    Added(String),   // Only in the second file
+}
+
+/// Reads both files and compares them line by line. Fails with a message when either
+/// can't be read, isn't UTF-8 text or is too big to compare here.
+pub fn diff_files(a: &Path, b: &Path) -> Result<Vec<Line>, String> {
+    let read = |path: &Path| {
+        let len = fs::metadata(long_path(path)).map_err(|err| describe(path, err))?.len();
+        if len > MAX_BYTES {
+            return Err(format!("{} is too large to compare here", display_path(path)));
+        }
+        let bytes = fs::read(long_path(path)).map_err(|err| describe(path, err))?;
+        String::from_utf8(bytes).map_err(|_| format!("{} is not a text file", display_path(path)))
+    };
+    let (old, new) = (read(a)?, read(b)?);
+    if old.lines().count() > MAX_LINES || new.lines().count() > MAX_LINES {
+        return Err("The files have too many lines to compare here".to_string());
+    }
+    Ok(diff_lines(&old, &new))
+}
+
+fn describe(path: &Path, err: std::io::Error) -> String {
+    format!("Cannot read {}: {}", display_path(path), err)
+}
+
+/// The lines of `old` and `new` as a shortest edit: lines both share in order are
+/// `Same`, the rest `Removed` from `old` or `Added` in `new`.
+pub fn diff_lines(old: &str, new: &str) -> Vec<Line> {
+    let (old, new): (Vec<&str>, Vec<&str>) = (old.lines().collect(), new.lines().collect());
+    // Only the middle, past any common start and end, needs the table
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (a, b) = (&old[prefix..old.len() - suffix], &new[prefix..new.len() - suffix]);
+
+    // common[i][j]: length of the longest common subsequence of a[i..] and b[j..]
+    let mut common = vec![vec![0u32; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            common[i][j] = if a[i] == b[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines: Vec<Line> =
+        old[..prefix].iter().map(|line| Line::Same(line.to_string())).collect();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            lines.push(Line::Same(a[i].to_string()));
+            (i, j) = (i + 1, j + 1);
+        } else if i < a.len() && (j == b.len() || common[i + 1][j] >= common[i][j + 1]) {
+            // On a tie the removal goes first, as diff tools show it
+            lines.push(Line::Removed(a[i].to_string()));
+            i += 1;
+        } else {
+            lines.push(Line::Added(b[j].to_string()));
+            j += 1;
+        }
+    }
+    lines.extend(old[old.len() - suffix..].iter().map(|line| Line::Same(line.to_string())));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_lines_keeps_shared_lines_and_marks_the_rest() {
+        let lines = diff_lines("a\nb\nc\nd\n", "a\nc\nx\nd\n");
+        assert_eq!(
+            lines,
+            [
+                Line::Same("a".into()),
+                Line::Removed("b".into()),
+                Line::Same("c".into()),
+                Line::Added("x".into()),
+                Line::Same("d".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_lines_of_identical_text_is_all_same() {
+        let lines = diff_lines("one\ntwo\n", "one\ntwo\n");
+        assert!(lines.iter().all(|line| matches!(line, Line::Same(_))));
+        assert_eq!(lines.len(), 2);
+    }
+
+    #[test]
+    fn diff_lines_handles_an_empty_side() {
+        assert_eq!(diff_lines("", "new\n"), [Line::Added("new".into())]);
+        assert_eq!(diff_lines("old\n", ""), [Line::Removed("old".into())]);
+    }
+}
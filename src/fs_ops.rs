@@ -0,0 +1,1579 @@
+//! Filesystem logic behind the explorer: reading folders and their entries, ordering
+//! them, copying, moving, renaming and deleting them, and working with paths, typed or
+//! pasted. Nothing here depends on iced.
+
+use rayon::prelude::*;
+use std::{
+    collections::{HashMap, HashSet},
+    env, fs, io,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+    time::SystemTime,
+};
+
+/// Column the file list is ordered by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortKey {
+    #[default]
+    Name,
+    Size,
+    Modified,
+}
+
+/// What the list shows about an entry, read once per listing so redraws never stat.
+#[derive(Debug, Clone, Default)]
+pub struct EntryMeta {
+    pub is_dir: bool,  // Of the link's target, for symlinks
+    pub size: Option<u64>,  // Files only; `None` when it can't be read
+    pub modified: Option<SystemTime>,
+    pub hidden: bool,  // Left out of the list unless "Show hidden" is on
+    pub is_symlink: bool,
+    pub link_target: Option<PathBuf>,  // Where a symlink points, as stored in the link
+    pub resolved_target: Option<PathBuf>,  // The same made absolute; `None` if it can't be
+    pub broken_link: bool,  // A symlink whose target doesn't exist
+    pub locked: bool,  // A folder we aren't allowed to list
+}
+
+impl EntryMeta {
+    /// Stands in for entries with no metadata, e.g. `..`.
+    pub const UNKNOWN: EntryMeta = EntryMeta {
+        is_dir: false,
+        size: None,
+        modified: None,
+        hidden: false,
+        is_symlink: false,
+        link_target: None,
+        resolved_target: None,
+        broken_link: false,
+        locked: false,
+    };
+
+    pub fn read(path: &Path) -> Self {
+        let path_long = long_path(path);
+        // `metadata` follows links, so ask about the link itself first
+        let is_symlink =
+            fs::symlink_metadata(&path_long).is_ok_and(|meta| meta.file_type().is_symlink());
+        let link_target = if is_symlink { fs::read_link(&path_long).ok() } else { None };
+        let resolved_target = if is_symlink { fs::canonicalize(&path_long).ok() } else { None };
+
+        match fs::metadata(&path_long) {
+            Ok(meta) => EntryMeta {
+                is_dir: meta.is_dir(),
+                size: if meta.is_dir() { None } else { Some(meta.len()) },
+                modified: meta.modified().ok(),
+                hidden: is_hidden(path, Some(&meta)),
+                is_symlink,
+                link_target,
+                resolved_target,
+                broken_link: false,
+                locked: meta.is_dir() && is_locked_dir(&path_long),
+            },
+            // Still listed, but all we can show is a dash (and, for links, that it's broken)
+            Err(_) => EntryMeta {
+                hidden: is_hidden(path, None),
+                is_symlink,
+                link_target,
+                resolved_target,
+                broken_link: is_symlink,
+                ..EntryMeta::UNKNOWN
+            },
+        }
+    }
+
+    /// Reads the metadata of every entry in `entries` except `..`, several at a time.
+    pub fn read_all(entries: &[PathBuf]) -> HashMap<PathBuf, EntryMeta> {
+        entries
+            .par_iter()
+            .filter(|entry| *entry != Path::new(".."))
+            .map(|entry| (entry.clone(), EntryMeta::read(entry)))
+            .collect()
+    }
+}
+
+/// Reads the entries of `path`, unsorted, together with their metadata. Hidden
+/// entries are left out unless `show_hidden` is set.
+///
+/// Entries are stat-ed across a thread pool, since one at a time is slow for big folders
+/// and network drives. Setting `cancel` abandons the work, which then fails with
+/// `Interrupted`.
+pub fn list_files_in_directory(
+    path: &Path,
+    show_hidden: bool,
+    cancel: &AtomicBool,
+) -> io::Result<Vec<(PathBuf, EntryMeta)>> {
+    let paths: Vec<PathBuf> = fs::read_dir(path)?.flatten().map(|entry| entry.path()).collect();
+    // `None` once cancelled, which stops the remaining reads
+    let entries: Option<Vec<(PathBuf, EntryMeta)>> = paths
+        .into_par_iter()
+        .map(|path| {
+            if cancel.load(Ordering::Relaxed) {
+                return None;
+            }
+            let meta = EntryMeta::read(&path);
+            Some((path, meta))
+        })
+        .collect();
+    let mut entries = entries
+        .ok_or_else(|| io::Error::new(io::ErrorKind::Interrupted, "listing was cancelled"))?;
+    entries.retain(|(_, meta)| show_hidden || !meta.hidden);
+    Ok(entries)
+}
+
+/// Recursively collects the files (no directories) below `root`, descending at most
+/// `max_depth` levels and stopping once `max_files` have been found. Hidden files are
+/// left out, and hidden folders not entered, unless `show_hidden` is set. Symlinked
+/// directories are not followed so cycles can't trap the walk.
+pub fn walk_files(
+    root: &Path,
+    show_hidden: bool,
+    max_depth: usize,
+    max_files: usize,
+) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut pending = vec![(root.to_path_buf(), 0)];
+
+    while let Some((dir, depth)) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if !show_hidden && is_hidden(&entry.path(), entry.metadata().ok().as_ref()) {
+                continue;
+            }
+            if file_type.is_dir() {
+                if depth < max_depth {
+                    pending.push((entry.path(), depth + 1));
+                }
+            } else {
+                files.push(entry.path());
+                if files.len() >= max_files {
+                    files.sort();
+                    return files;
+                }
+            }
+        }
+    }
+
+    files.sort();
+    files
+}
+
+/// Sums the sizes of all files below `root` without following symlinks. Unreadable
+/// subfolders are skipped. Returns `None` if `cancel` was set part-way.
+pub fn dir_size(root: &Path, cancel: &AtomicBool) -> Option<u64> {
+    let mut total = 0;
+    let mut pending = vec![long_path(root)];
+    while let Some(dir) = pending.pop() {
+        if cancel.load(Ordering::Relaxed) {
+            return None;
+        }
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            match entry.metadata() {
+                Ok(meta) if meta.is_dir() => pending.push(entry.path()),
+                Ok(meta) => total += meta.len(),
+                Err(_) => {}
+            }
+        }
+    }
+    Some(total)
+}
+
+/// Lists the folders next to `path`, i.e. its parent's subfolders (including `path`),
+/// in natural name order.
+pub fn sibling_folders(path: &Path) -> Vec<PathBuf> {
+    let Some(parent) = path.parent() else {
+        return Vec::new();
+    };
+    let mut siblings: Vec<PathBuf> = fs::read_dir(parent)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|entry| entry.is_dir())
+                .collect()
+        })
+        .unwrap_or_default();
+    let name = |path: &PathBuf| {
+        path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default()
+    };
+    siblings.sort_by(|a, b| natural_cmp(&name(a), &name(b)));
+    siblings
+}
+
+/// Orders directory entries by `key`, with `..` pinned first and folders always ahead
+/// of files. Entries whose size or modified time can't be read sort after the rest in
+/// either direction, and ties fall back to the name.
+pub fn sort_entries(
+    entries: &mut Vec<PathBuf>,
+    meta: &HashMap<PathBuf, EntryMeta>,
+    key: SortKey,
+    ascending: bool,
+) {
+    let mut keyed: Vec<_> = entries
+        .drain(..)
+        .map(|path| {
+            let meta = meta.get(&path).unwrap_or(&EntryMeta::UNKNOWN);
+            let value = match key {
+                SortKey::Name => Some(0),
+                // Folders have no meaningful size here, so they stay in name order
+                SortKey::Size if meta.is_dir => Some(0),
+                SortKey::Size => meta.size.map(u128::from),
+                SortKey::Modified => meta
+                    .modified
+                    .and_then(|time| time.duration_since(SystemTime::UNIX_EPOCH).ok())
+                    .map(|since_epoch| since_epoch.as_nanos()),
+            };
+            (meta.is_dir, value, path)
+        })
+        .collect();
+
+    keyed.sort_by(|(a_dir, a_value, a_path), (b_dir, b_value, b_path)| {
+        let by_name = || {
+            let name = |path: &PathBuf| {
+                path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default()
+            };
+            natural_cmp(&name(a_path), &name(b_path))
+        };
+        let directed = |ordering: std::cmp::Ordering| {
+            if ascending { ordering } else { ordering.reverse() }
+        };
+
+        let is_parent = |path: &PathBuf| path == Path::new("..");
+        let pinned = is_parent(b_path).cmp(&is_parent(a_path));
+        pinned.then_with(|| b_dir.cmp(a_dir)).then_with(|| match key {
+            SortKey::Name => directed(by_name()),
+            SortKey::Size | SortKey::Modified => match (a_value, b_value) {
+                (Some(a), Some(b)) => directed(a.cmp(b)).then_with(by_name),
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (None, None) => by_name(),
+            },
+        })
+    });
+
+    entries.extend(keyed.into_iter().map(|(_, _, path)| path));
+}
+
+/// Compares names the way people read them: runs of digits by their numeric value (so
+/// `img2` comes before `img10`) and everything else case-insensitively. Names that only
+/// differ in case or leading zeros fall back to a plain comparison, so the order is total.
+pub fn natural_cmp(a: &str, b: &str) -> std::cmp::Ordering {
+    use std::cmp::Ordering;
+
+    fn digit_run(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+        let mut run = String::new();
+        while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+            run.push(digit);
+        }
+        run
+    }
+
+    let (mut a_chars, mut b_chars) = (a.chars().peekable(), b.chars().peekable());
+    loop {
+        let ordering = match (a_chars.peek(), b_chars.peek()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let (x, y) = (digit_run(&mut a_chars), digit_run(&mut b_chars));
+                // Compare as numbers without parsing, so any length of run works
+                let (x, y) = (x.trim_start_matches('0'), y.trim_start_matches('0'));
+                x.len().cmp(&y.len()).then_with(|| x.cmp(y))
+            }
+            (Some(_), Some(_)) => {
+                let (x, y) = (a_chars.next().unwrap(), b_chars.next().unwrap());
+                x.to_lowercase().cmp(y.to_lowercase())
+            }
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+/// Whether the OS would normally hide `path`: a dotfile on Unix, an entry with the
+/// hidden attribute on Windows.
+pub fn is_hidden(path: &Path, meta: Option<&fs::Metadata>) -> bool {
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::MetadataExt;
+        const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+        let _ = path;
+        meta.is_some_and(|meta| meta.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0)
+    }
+    #[cfg(not(windows))]
+    {
+        let _ = meta;
+        path.file_name().is_some_and(|name| name.to_string_lossy().starts_with('.'))
+    }
+}
+
+/// Whether listing the folder at `path` would be refused. Only Unix can tell without
+/// trying, from the permission bits; elsewhere nothing is reported as locked.
+#[cfg(unix)]
+fn is_locked_dir(path: &Path) -> bool {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+    let Ok(c_path) = CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+    // Listing needs read permission on the folder, and entering it needs execute.
+    // SAFETY: access only reads the NUL-terminated path we hand it
+    let refused = unsafe { libc::access(c_path.as_ptr(), libc::R_OK | libc::X_OK) } != 0;
+    refused && io::Error::last_os_error().raw_os_error() == Some(libc::EACCES)
+}
+
+#[cfg(not(unix))]
+fn is_locked_dir(_path: &Path) -> bool {
+    false
+}
+
+/// The path an entry listed in `dir` stands for: `..` is `dir`'s parent (or `dir`
+/// itself at a root), a relative entry is joined onto `dir`, and an absolute one is
+/// already complete.
+pub fn resolve_entry(dir: &Path, entry: &Path) -> PathBuf {
+    if entry == Path::new("..") {
+        dir.parent().map_or(dir.to_path_buf(), |parent| parent.to_path_buf())
+    } else if entry.is_relative() {
+        dir.join(entry)
+    } else {
+        entry.to_path_buf()
+    }
+}
+
+/// Whether `a` and `b` name the same entry on disk, as a case-only rename does on a
+/// case-insensitive filesystem. On Unix a link is compared as itself, not its target.
+#[cfg(unix)]
+pub fn same_entry(a: &Path, b: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    match (fs::symlink_metadata(a), fs::symlink_metadata(b)) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+        _ => false,
+    }
+}
+
+#[cfg(not(unix))]
+pub fn same_entry(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Pre-flight check that files can be created in `dir`, so a paste into a read-only
+/// destination fails up front instead of part-way through.
+pub fn check_writable(dir: &Path) -> io::Result<()> {
+    let probe = dir.join(format!(".fex-write-test-{}", std::process::id()));
+    match fs::OpenOptions::new().write(true).create_new(true).open(&probe) {
+        Ok(_) => fs::remove_file(&probe),
+        Err(err) if matches!(
+            err.kind(),
+            io::ErrorKind::PermissionDenied | io::ErrorKind::ReadOnlyFilesystem
+        ) => Err(io::Error::new(
+            err.kind(),
+            format!("destination is read-only: {}", dir.display()),
+        )),
+        Err(err) => Err(err),
+    }
+}
+
+/// What pasting entries does with them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClipOp {
+    Copy,
+    Cut,  // Move: the originals go away once pasted
+}
+
+/// How to carry out a paste, worked out by `plan_paste` and then done a stretch at a
+/// time by `paste_batch`.
+#[derive(Debug, Clone, Default)]
+pub struct PastePlan {
+    pub steps: Vec<PasteStep>,
+    pub progress: PasteProgress,  // Entries already moved or refused while planning
+}
+
+/// One thing to do for one of the pasted entries.
+#[derive(Debug, Clone)]
+pub struct PasteStep {
+    entry: usize,  // Index of the pasted entry this is part of
+    action: PasteAction,
+}
+
+#[derive(Debug, Clone)]
+enum PasteAction {
+    CreateDir(PathBuf),
+    CopyFile(PathBuf, PathBuf),
+    CopyLink(PathBuf, PathBuf),
+    // Last step of every entry: removes the original of a move once it's all copied,
+    // or the partial copy if something went wrong
+    Finish { source: PathBuf, dest: PathBuf, op: ClipOp },
+}
+
+/// What a paste has come to so far, carried from one `paste_batch` to the next.
+#[derive(Debug, Clone, Default)]
+pub struct PasteProgress {
+    pub pasted: usize,  // Entries that arrived in full
+    pub failed: HashMap<usize, String>,  // Why each failed entry (by index) did
+    written: HashSet<usize>,  // Entries that have something at their destination
+}
+
+/// Works out how to copy or move `sources` into `dest_dir`. Names already taken there
+/// get a " (copy)" suffix rather than replacing anything. Moves within a filesystem
+/// are just renames and happen right here; copies, and moves across filesystems, are
+/// planned file by file so they can run with progress. Fails outright only when
+/// `dest_dir` can't be written to. Gives up early, with a partial plan, once `cancel`
+/// is set.
+pub fn plan_paste(
+    sources: &[PathBuf],
+    dest_dir: &Path,
+    op: ClipOp,
+    cancel: &AtomicBool,
+) -> Result<PastePlan, String> {
+    let dest_dir = long_path(dest_dir);
+    check_writable(&dest_dir).map_err(|err| err.to_string())?;
+    let mut plan = PastePlan::default();
+    let mut reserved = HashSet::new();
+    for (entry, source) in sources.iter().enumerate() {
+        if cancel.load(Ordering::Relaxed) {
+            break;
+        }
+        let planned = plan_entry(entry, &long_path(source), &dest_dir, op, &mut reserved);
+        match planned {
+            Ok(Some(steps)) => plan.steps.extend(steps),
+            Ok(None) => plan.progress.pasted += 1,
+            Err(err) => {
+                plan.progress.failed.insert(entry, err.to_string());
+            }
+        }
+    }
+    Ok(plan)
+}
+
+/// The steps pasting `source` takes, or `None` when it's already done. `reserved` holds
+/// the destinations earlier entries of the same paste were given.
+fn plan_entry(
+    entry: usize,
+    source: &Path,
+    dest_dir: &Path,
+    op: ClipOp,
+    reserved: &mut HashSet<PathBuf>,
+) -> io::Result<Option<Vec<PasteStep>>> {
+    let name = source.file_name().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, "path has no file name")
+    })?;
+    let Ok(meta) = fs::symlink_metadata(source) else {
+        return Err(io::Error::new(io::ErrorKind::NotFound, "no longer exists"));
+    };
+    // Pasting a folder into itself would never finish
+    if meta.is_dir() && dest_dir.starts_with(source) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "cannot paste a folder into itself",
+        ));
+    }
+    // Cut and pasted back where it came from: nothing to do
+    if op == ClipOp::Cut && source.parent() == Some(dest_dir) {
+        return Ok(None);
+    }
+
+    let dest = unique_name(dest_dir, name, reserved);
+    reserved.insert(dest.clone());
+    if op == ClipOp::Cut {
+        match fs::rename(source, &dest) {
+            Ok(()) => return Ok(None),
+            // Across filesystems a move is a copy followed by a delete
+            Err(err) if crosses_devices(&err) => {}
+            Err(err) => return Err(explain_locked(err, source)),
+        }
+    }
+    plan_copy(entry, source, &dest, op).map(Some)
+}
+
+/// Whether `err` is a rename refused because the two paths are on different
+/// filesystems: EXDEV on Unix, ERROR_NOT_SAME_DEVICE on Windows.
+fn crosses_devices(err: &io::Error) -> bool {
+    err.kind() == io::ErrorKind::CrossesDevices
+        || err.raw_os_error() == Some(if cfg!(windows) { 17 } else { 18 })
+}
+
+/// The steps copying `source` to `dest`: each folder before its contents, symlinks as
+/// links rather than what they point at, and last the entry's `Finish`, which for a
+/// move removes the original. Folders are read now, so an unreadable one fails the
+/// whole entry before anything is copied.
+fn plan_copy(entry: usize, source: &Path, dest: &Path, op: ClipOp) -> io::Result<Vec<PasteStep>> {
+    let mut steps = Vec::new();
+    let mut pending = vec![(source.to_path_buf(), dest.to_path_buf())];
+    while let Some((from, to)) = pending.pop() {
+        let meta = fs::symlink_metadata(&from)?;
+        let action = if meta.file_type().is_symlink() {
+            PasteAction::CopyLink(from, to)
+        } else if meta.is_dir() {
+            for child in fs::read_dir(&from)? {
+                let child = child?;
+                pending.push((child.path(), to.join(child.file_name())));
+            }
+            PasteAction::CreateDir(to)
+        } else {
+            PasteAction::CopyFile(from, to)
+        };
+        steps.push(PasteStep { entry, action });
+    }
+    let finish = PasteAction::Finish { source: source.to_path_buf(), dest: dest.to_path_buf(), op };
+    steps.push(PasteStep { entry, action: finish });
+    Ok(steps)
+}
+
+/// Carries out `steps`, a stretch of what `plan_paste` returned, in order. Once a step
+/// of an entry fails the rest of that entry is skipped, and its `Finish` removes what
+/// was copied of it, so a failed move leaves the original alone. Once `cancel` is set,
+/// the entry under way is undone the same way and the rest left; the number of steps
+/// dealt with is returned, fewer than `steps.len()` if cancelled.
+pub fn paste_batch(
+    steps: &[PasteStep],
+    progress: &mut PasteProgress,
+    cancel: &AtomicBool,
+) -> usize {
+    for (done, step) in steps.iter().enumerate() {
+        let entry = step.entry;
+        if cancel.load(Ordering::Relaxed) && !progress.failed.contains_key(&entry) {
+            if !progress.written.contains(&entry) {
+                return done;
+            }
+            progress.failed.insert(entry, "cancelled".to_string());
+        }
+        let result = match &step.action {
+            PasteAction::Finish { source, dest, op } => {
+                finish_entry(entry, source, dest, *op, progress);
+                continue;
+            }
+            _ if progress.failed.contains_key(&entry) => continue,
+            PasteAction::CreateDir(to) => fs::create_dir(to),
+            PasteAction::CopyFile(from, to) => copy_file(from, to),
+            PasteAction::CopyLink(from, to) => copy_link(from, to),
+        };
+        match result {
+            Ok(()) => {
+                progress.written.insert(entry);
+            }
+            Err(err) => {
+                // Something may have been left behind, unless the name was taken
+                if err.kind() != io::ErrorKind::AlreadyExists {
+                    progress.written.insert(entry);
+                }
+                progress.failed.insert(entry, explain_locked(err, step.source()).to_string());
+            }
+        }
+    }
+    steps.len()
+}
+
+impl PasteStep {
+    /// The path this step reads from, for error messages.
+    fn source(&self) -> &Path {
+        match &self.action {
+            PasteAction::CreateDir(path) => path,
+            PasteAction::CopyFile(from, _) | PasteAction::CopyLink(from, _) => from,
+            PasteAction::Finish { source, .. } => source,
+        }
+    }
+}
+
+/// The last step of one entry: a failed entry's partial copy is removed, and a moved
+/// entry that arrived in full has its original removed.
+fn finish_entry(
+    entry: usize,
+    source: &Path,
+    dest: &Path,
+    op: ClipOp,
+    progress: &mut PasteProgress,
+) {
+    if progress.failed.contains_key(&entry) {
+        if progress.written.remove(&entry) {
+            let _ = remove_entry(dest);
+        }
+        return;
+    }
+    progress.written.remove(&entry);
+    if op == ClipOp::Cut
+        && let Err(err) = remove_entry(source)
+    {
+        let err = explain_locked(err, source);
+        progress.failed.insert(entry, format!("copied, but the original was kept: {}", err));
+        return;
+    }
+    progress.pasted += 1;
+}
+
+/// Copies the file at `from` to `to`, refusing to replace anything already there.
+fn copy_file(from: &Path, to: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(to).is_ok() {
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists, "already exists"));
+    }
+    fs::copy(from, to).map(|_| ())
+}
+
+/// `name` inside `dir`, or if that's taken the first free of "name (copy).ext",
+/// "name (copy 2).ext", and so on. Names in `reserved` count as taken.
+fn unique_name(dir: &Path, name: &std::ffi::OsStr, reserved: &HashSet<PathBuf>) -> PathBuf {
+    // symlink_metadata so a dangling link still counts as taken
+    let taken = |path: &Path| reserved.contains(path) || fs::symlink_metadata(path).is_ok();
+    let candidate = dir.join(name);
+    if !taken(&candidate) {
+        return candidate;
+    }
+
+    let name = Path::new(name);
+    let stem = name.file_stem().unwrap_or(name.as_os_str());
+    for attempt in 1.. {
+        let mut new_name = stem.to_os_string();
+        if attempt == 1 {
+            new_name.push(" (copy)");
+        } else {
+            new_name.push(format!(" (copy {})", attempt));
+        }
+        if let Some(extension) = name.extension() {
+            new_name.push(".");
+            new_name.push(extension);
+        }
+        let candidate = dir.join(new_name);
+        if !taken(&candidate) {
+            return candidate;
+        }
+    }
+    unreachable!("ran out of copy numbers")
+}
+
+/// Makes `dest` a symlink to wherever the link at `source` points.
+fn copy_link(source: &Path, dest: &Path) -> io::Result<()> {
+    let target = fs::read_link(source)?;
+    #[cfg(unix)]
+    return std::os::unix::fs::symlink(target, dest);
+    #[cfg(windows)]
+    {
+        // Windows has separate kinds of link for folders and files
+        if fs::metadata(source).is_ok_and(|meta| meta.is_dir()) {
+            std::os::windows::fs::symlink_dir(target, dest)
+        } else {
+            std::os::windows::fs::symlink_file(target, dest)
+        }
+    }
+    #[cfg(not(any(unix, windows)))]
+    {
+        let _ = (target, dest);
+        Err(io::Error::new(io::ErrorKind::Unsupported, "cannot create symlinks here"))
+    }
+}
+
+/// Removes a file, a symlink or a whole directory tree. A symlink is removed itself,
+/// never the folder it points at.
+fn remove_entry(path: &Path) -> io::Result<()> {
+    match fs::symlink_metadata(path) {
+        Ok(meta) if meta.is_dir() => fs::remove_dir_all(path),
+        _ => fs::remove_file(path),
+    }
+}
+
+/// `path` with its extension replaced by `extension`, given with or without the dot,
+/// or gaining it when there was none. An empty `extension` removes the old one.
+pub fn replace_extension(path: &Path, extension: &str) -> PathBuf {
+    path.with_extension(extension.trim_start_matches('.'))
+}
+
+/// Checks a batch of `(from, to)` renames before any is made: no two may end up with
+/// the same name, and none may land on an entry that's already there (including one
+/// the batch itself is about to rename away, since the order would then matter).
+pub fn check_renames(renames: &[(PathBuf, PathBuf)]) -> Result<(), String> {
+    let mut targets = HashSet::new();
+    for (from, to) in renames {
+        let name = to.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+        if !targets.insert(to) {
+            return Err(format!("More than one file would be named \"{}\"", name));
+        }
+        if fs::symlink_metadata(long_path(to)).is_ok() && !same_entry(from, to) {
+            return Err(format!("\"{}\" already exists", name));
+        }
+    }
+    Ok(())
+}
+
+/// Renames each `(from, to)` pair in order. If one fails, the ones already made are
+/// undone, last first, so the batch is applied completely or not at all; the error
+/// says which rename failed and whether any couldn't be undone.
+pub fn rename_all(renames: &[(PathBuf, PathBuf)]) -> io::Result<()> {
+    for (done, (from, to)) in renames.iter().enumerate() {
+        let Err(err) = fs::rename(long_path(from), long_path(to)) else {
+            continue;
+        };
+        let stuck = renames[..done]
+            .iter()
+            .rev()
+            .filter(|(from, to)| fs::rename(long_path(to), long_path(from)).is_err())
+            .count();
+        let mut message = format!("{}: {}", display_path(from), err);
+        if stuck > 0 {
+            message.push_str(&format!(" ({} earlier renames could not be undone)", stuck));
+        }
+        return Err(io::Error::new(err.kind(), message));
+    }
+    Ok(())
+}
+
+/// What became of one entry of a delete.
+#[derive(Debug, Clone)]
+pub enum DeleteOutcome {
+    Deleted,
+    Failed(String),
+    Kept,  // A folder left alone because something inside it couldn't be deleted
+}
+
+/// Everything deleting `targets` has to remove, children before the folder holding
+/// them, so each folder is empty by the time its turn comes. Symlinks are listed, not
+/// followed. Gives up early, with a partial list, once `cancel` is set.
+pub fn plan_delete(targets: &[PathBuf], cancel: &AtomicBool) -> Vec<PathBuf> {
+    let mut items = Vec::new();
+    // A folder is pushed back, marked as visited, so it comes out after its contents
+    let mut pending: Vec<(PathBuf, bool)> =
+        targets.iter().rev().map(|target| (target.clone(), false)).collect();
+    while let Some((path, visited)) = pending.pop() {
+        if cancel.load(Ordering::Relaxed) {
+            break;
+        }
+        let is_dir = fs::symlink_metadata(&path).is_ok_and(|meta| meta.is_dir());
+        if visited || !is_dir {
+            items.push(path);
+            continue;
+        }
+        pending.push((path.clone(), true));
+        // An unreadable folder is still listed; removing it reports why it can't go
+        if let Ok(entries) = fs::read_dir(&path) {
+            pending.extend(entries.flatten().map(|entry| (entry.path(), false)));
+        }
+    }
+    items
+}
+
+/// Deletes `items`, a stretch of what `plan_delete` returned, in order and carrying on
+/// past failures. A folder something inside couldn't be deleted from is kept without
+/// trying, and so are the folders above it; `blocked` carries those from one stretch to
+/// the next. Stops early once `cancel` is set, so fewer outcomes than items may return.
+pub fn delete_batch(
+    items: &[PathBuf],
+    blocked: &mut HashSet<PathBuf>,
+    cancel: &AtomicBool,
+) -> Vec<(PathBuf, DeleteOutcome)> {
+    let mut outcomes = Vec::with_capacity(items.len());
+    for path in items {
+        if cancel.load(Ordering::Relaxed) {
+            break;
+        }
+        let outcome = if blocked.contains(path) {
+            DeleteOutcome::Kept
+        } else {
+            // Don't follow a symlink into the folder it points at
+            let result = match fs::symlink_metadata(long_path(path)) {
+                Ok(meta) if meta.is_dir() => fs::remove_dir(long_path(path)),
+                Ok(_) => fs::remove_file(long_path(path)),
+                Err(err) => Err(err),
+            };
+            match result {
+                Ok(()) => DeleteOutcome::Deleted,
+                Err(err) => DeleteOutcome::Failed(explain_locked(err, path).to_string()),
+            }
+        };
+        if !matches!(outcome, DeleteOutcome::Deleted) {
+            for ancestor in path.ancestors().skip(1) {
+                if !blocked.insert(ancestor.to_path_buf()) {
+                    break;
+                }
+            }
+        }
+        outcomes.push((path.clone(), outcome));
+    }
+    outcomes
+}
+
+/// Rewrites Windows sharing/lock violations (another process holds the file open) into
+/// an error naming the file, since the raw OS message doesn't say which one it was.
+fn explain_locked(err: io::Error, path: &Path) -> io::Error {
+    // ERROR_SHARING_VIOLATION and ERROR_LOCK_VIOLATION
+    if cfg!(windows) && matches!(err.raw_os_error(), Some(32) | Some(33)) {
+        io::Error::new(
+            err.kind(),
+            format!("{} is in use by another process", path.display()),
+        )
+    } else {
+        err
+    }
+}
+
+/// The user's home folder: `HOME` on Unix or `USERPROFILE` on Windows, falling back
+/// to the platform lookup in `dirs` when the variable is unset or empty.
+pub fn home_dir() -> Option<PathBuf> {
+    let var = if cfg!(windows) { "USERPROFILE" } else { "HOME" };
+    env::var_os(var)
+        .filter(|home| !home.is_empty())
+        .map(PathBuf::from)
+        .or_else(dirs::home_dir)
+}
+
+/// Resolves what the user typed as a location: `~` expands to the home directory,
+/// environment variables expand (see `expand_env_vars`), and relative paths are taken
+/// against `base`. If the expanded path doesn't exist but the text as typed does, the
+/// text wins. Fails with a message naming the missing path, along with any variables
+/// that weren't set.
+pub fn expand_path(input: &str, base: &Path) -> Result<PathBuf, String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err("Enter a path".to_string());
+    }
+
+    let resolve = |text: &str| -> Result<PathBuf, String> {
+        let path = if text == "~" {
+            home_dir().ok_or("Could not determine the home directory")?
+        } else if let Some(rest) = text.strip_prefix("~/").or_else(|| text.strip_prefix("~\\")) {
+            home_dir()
+                .ok_or("Could not determine the home directory")?
+                .join(rest)
+        } else {
+            PathBuf::from(text)
+        };
+        Ok(if path.is_relative() { base.join(path) } else { path })
+    };
+
+    let (expanded, unset) = expand_env_vars(input, |name| env::var(name).ok());
+    let path = resolve(&expanded)?;
+    if path.exists() {
+        return Ok(path);
+    }
+    // A real name can look like a variable, e.g. `$HOME` as a folder name
+    let literal = resolve(input)?;
+    if expanded != input && literal.exists() {
+        return Ok(literal);
+    }
+    Err(match unset.as_slice() {
+        [] => format!("No such file or folder: {}", path.display()),
+        names => format!(
+            "No such file or folder: {} ({} not set)",
+            path.display(),
+            names.join(", ")
+        ),
+    })
+}
+
+/// Replaces `$VAR` and `${VAR}` references, and on Windows `%VAR%` ones too, with the
+/// values `lookup` gives. A reference to a variable that isn't set is kept as typed,
+/// since plenty of real names look like one (`$Recycle.Bin`, `100%done`); those names
+/// come back alongside the text. A lone `$` or `%` is kept as is.
+fn expand_env_vars(
+    input: &str,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> (String, Vec<String>) {
+    let markers: &[char] = if cfg!(windows) { &['$', '%'] } else { &['$'] };
+    let mut output = String::with_capacity(input.len());
+    let mut unset = Vec::new();
+    let mut rest = input;
+    while let Some(start) = rest.find(markers) {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+
+        let (name, remaining) = if rest[start..].starts_with('%') {
+            match after.find('%') {
+                Some(end) if end > 0 => (&after[..end], &after[end + 1..]),
+                _ => ("", after),
+            }
+        } else if let Some(braced) = after.strip_prefix('{') {
+            match braced.find('}') {
+                Some(end) => (&braced[..end], &braced[end + 1..]),
+                None => ("", after),
+            }
+        } else {
+            let end = after
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(after.len());
+            (&after[..end], &after[end..])
+        };
+
+        if name.is_empty() {
+            output.push_str(&rest[start..start + 1]);
+            rest = after;
+            continue;
+        }
+        match lookup(name) {
+            Some(value) => output.push_str(&value),
+            None => {
+                output.push_str(&rest[start..rest.len() - remaining.len()]);
+                unset.push(name.to_string());
+            }
+        }
+        rest = remaining;
+    }
+    output.push_str(rest);
+    (output, unset)
+}
+
+/// Turns OS clipboard text into the paths it refers to. Accepts plain
+/// newline-separated paths (optionally quoted, as Windows' "Copy as path" does) as
+/// well as `text/uri-list` content with `file://` URIs and `#` comment lines.
+pub fn parse_clipboard_paths(text: &str) -> Vec<PathBuf> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let line = line.trim_matches('"');
+            if let Some(uri) = line.strip_prefix("file://") {
+                // Skip the authority ("localhost" or empty) up to the path
+                let path = &uri[uri.find('/')?..];
+                let path = percent_decode(path)?;
+                // file:///C:/dir on Windows carries a leading slash before the drive
+                if cfg!(windows) && path.len() > 2 && path.as_bytes()[2] == b':' {
+                    return Some(PathBuf::from(&path[1..]));
+                }
+                Some(PathBuf::from(path))
+            } else if line.contains("://") {
+                None
+            } else {
+                Some(PathBuf::from(line))
+            }
+        })
+        .collect()
+}
+
+/// Decodes `%XX` escapes in a URI path, returning `None` on malformed input.
+fn percent_decode(input: &str) -> Option<String> {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = input.get(i + 1..i + 3)?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+/// Roots the drive picker offers: drive letters on Windows, `/` plus mounted volumes
+/// elsewhere.
+pub fn get_available_drives() -> Vec<PathBuf> {
+    if cfg!(windows) {
+        (b'A'..=b'Z')
+            .map(|c| format!("{}:", c as char))
+            .map(PathBuf::from)
+            .filter(|drive| drive.exists())
+            .collect()
+    } else {
+        let mut drives = vec![PathBuf::from("/")];
+        if cfg!(target_os = "macos") {
+            // Every mounted volume, the boot disk included, appears under /Volumes
+            if let Ok(entries) = fs::read_dir("/Volumes") {
+                let mut volumes: Vec<PathBuf> =
+                    entries.flatten().map(|entry| entry.path()).collect();
+                volumes.sort();
+                drives.extend(volumes);
+            }
+        } else if let Ok(mounts) = fs::read_to_string("/proc/mounts") {
+            drives.extend(parse_mounts(&mounts));
+        }
+
+        // Keep the first occurrence so `/` stays at the front
+        let mut seen = HashSet::new();
+        drives.retain(|drive| seen.insert(drive.clone()));
+        drives
+    }
+}
+
+/// Extracts the mount points of real filesystems from `/proc/mounts` content,
+/// skipping kernel pseudo-filesystems that aren't useful to browse.
+pub fn parse_mounts(mounts: &str) -> Vec<PathBuf> {
+    const PSEUDO_FILESYSTEMS: &[&str] = &[
+        "proc", "sysfs", "tmpfs", "devtmpfs", "devpts", "cgroup", "cgroup2", "securityfs",
+        "pstore", "debugfs", "tracefs", "configfs", "fusectl", "mqueue", "hugetlbfs", "bpf",
+        "autofs", "binfmt_misc", "efivarfs", "rpc_pipefs", "nsfs", "ramfs", "squashfs",
+    ];
+
+    mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let _device = fields.next()?;
+            let (mount_point, fs_type) = (fields.next()?, fields.next()?);
+            if PSEUDO_FILESYSTEMS.contains(&fs_type) {
+                return None;
+            }
+            Some(PathBuf::from(unescape_mount_point(mount_point)))
+        })
+        .collect()
+}
+
+/// Undoes the octal escaping `/proc/mounts` applies to spaces, tabs, newlines and
+/// backslashes in mount points (e.g. `\040` for a space).
+fn unescape_mount_point(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let octal = field
+            .get(i + 1..i + 4)
+            .filter(|_| bytes[i] == b'\\')
+            .and_then(|digits| u8::from_str_radix(digits, 8).ok());
+        match octal {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 4;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+// Windows refuses most paths this long unless they use the extended-length form
+const MAX_PATH: usize = 260;
+
+/// Returns the form of `path` to hand to filesystem calls. On Windows, absolute paths at
+/// or past MAX_PATH get the `\\?\` extended-length prefix (`\\?\UNC\` for shares),
+/// and prefixed paths that have become short again (e.g. after going up) lose it.
+/// Elsewhere the path is returned unchanged.
+pub fn long_path(path: &Path) -> PathBuf {
+    if !cfg!(windows) {
+        return path.to_path_buf();
+    }
+
+    let plain = PathBuf::from(display_path(path));
+    let text = plain.to_string_lossy();
+    // The prefix turns off normalization, so `..` and `/` must not reach it
+    let normalizable = !plain
+        .components()
+        .any(|component| component == std::path::Component::ParentDir);
+    if text.len() < MAX_PATH || !plain.is_absolute() || !normalizable {
+        return plain;
+    }
+
+    let text = text.replace('/', "\\");
+    match text.strip_prefix("\\\\") {
+        Some(share) => PathBuf::from(format!("\\\\?\\UNC\\{}", share)),
+        None => PathBuf::from(format!("\\\\?\\{}", text)),
+    }
+}
+
+/// Renders a path for the user, hiding any extended-length prefix added by `long_path`.
+pub fn display_path(path: &Path) -> String {
+    let text = path.to_string_lossy();
+    if let Some(share) = text.strip_prefix("\\\\?\\UNC\\") {
+        format!("\\\\{}", share)
+    } else if let Some(rest) = text.strip_prefix("\\\\?\\") {
+        rest.to_string()
+    } else {
+        text.into_owned()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// An empty folder of its own for the test called `name`.
+    pub(crate) fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("fex-test-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn replace_extension_swaps_appends_and_ignores_the_dot() {
+        assert_eq!(replace_extension(Path::new("notes.txt"), ".md"), Path::new("notes.md"));
+        assert_eq!(replace_extension(Path::new("notes.txt"), "md"), Path::new("notes.md"));
+        assert_eq!(replace_extension(Path::new("README"), "md"), Path::new("README.md"));
+        assert_eq!(
+            replace_extension(Path::new("backup.tar.gz"), "zst"),
+            Path::new("backup.tar.zst")
+        );
+    }
+
+    #[test]
+    fn check_renames_refuses_collisions() {
+        let root = temp_dir("check-renames");
+        fs::write(root.join("a.txt"), "x").unwrap();
+        fs::write(root.join("a.log"), "x").unwrap();
+        fs::write(root.join("b.txt"), "x").unwrap();
+        fs::write(root.join("b.md"), "x").unwrap();
+
+        let into_md = |name: &str| (root.join(name), replace_extension(&root.join(name), "md"));
+        assert!(check_renames(&[into_md("a.txt")]).is_ok());
+        assert!(check_renames(&[into_md("a.txt"), into_md("a.log")]).is_err());
+        assert!(check_renames(&[into_md("b.txt")]).is_err());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn rename_all_undoes_earlier_renames_when_one_fails() {
+        let root = temp_dir("rename-all");
+        fs::write(root.join("a.txt"), "x").unwrap();
+        fs::write(root.join("b.txt"), "x").unwrap();
+
+        let renames = [
+            (root.join("a.txt"), root.join("a.md")),
+            (root.join("b.txt"), root.join("missing").join("b.md")),
+        ];
+        assert!(rename_all(&renames).is_err());
+        assert!(root.join("a.txt").exists());
+        assert!(!root.join("a.md").exists());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn plan_delete_lists_contents_before_their_folder() {
+        let root = temp_dir("plan-delete");
+        let target = root.join("target");
+        fs::create_dir_all(target.join("inner")).unwrap();
+        fs::write(target.join("inner").join("file.txt"), "x").unwrap();
+        fs::write(target.join("top.txt"), "x").unwrap();
+
+        let items = plan_delete(std::slice::from_ref(&target), &AtomicBool::new(false));
+        let position = |path: &Path| items.iter().position(|item| item == path).unwrap();
+        assert_eq!(items.len(), 4);
+        assert_eq!(items.last(), Some(&target));
+        assert!(position(&target.join("inner").join("file.txt")) < position(&target.join("inner")));
+
+        let outcomes = delete_batch(&items, &mut HashSet::new(), &AtomicBool::new(false));
+        assert!(outcomes.iter().all(|(_, outcome)| matches!(outcome, DeleteOutcome::Deleted)));
+        assert!(!target.exists());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn delete_batch_keeps_the_folders_above_a_kept_entry() {
+        let root = temp_dir("delete-blocked");
+        let target = root.join("target");
+        fs::create_dir_all(target.join("inner")).unwrap();
+        fs::write(target.join("other.txt"), "x").unwrap();
+
+        let items = plan_delete(std::slice::from_ref(&target), &AtomicBool::new(false));
+        // As if something inside `inner` had failed in an earlier batch
+        let mut blocked = HashSet::from([target.join("inner")]);
+        let outcomes = delete_batch(&items, &mut blocked, &AtomicBool::new(false));
+        let outcome_of = |path: &Path| &outcomes.iter().find(|(item, _)| item == path).unwrap().1;
+        assert!(matches!(outcome_of(&target.join("other.txt")), DeleteOutcome::Deleted));
+        assert!(matches!(outcome_of(&target.join("inner")), DeleteOutcome::Kept));
+        assert!(matches!(outcome_of(&target), DeleteOutcome::Kept));
+        assert!(target.join("inner").exists());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn delete_batch_stops_once_cancelled() {
+        let root = temp_dir("delete-cancel");
+        fs::write(root.join("file.txt"), "x").unwrap();
+
+        let items = vec![root.join("file.txt")];
+        let outcomes = delete_batch(&items, &mut HashSet::new(), &AtomicBool::new(true));
+        assert!(outcomes.is_empty());
+        assert!(root.join("file.txt").exists());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn display_path_hides_the_extended_length_prefix() {
+        assert_eq!(display_path(Path::new(r"\\?\C:\work\notes.txt")), r"C:\work\notes.txt");
+        assert_eq!(display_path(Path::new(r"\\?\UNC\server\share\a")), r"\\server\share\a");
+        assert_eq!(display_path(Path::new("/home/user")), "/home/user");
+    }
+
+    #[test]
+    fn listing_works_in_a_folder_past_max_path() {
+        let root = temp_dir("deep-path");
+        let mut deep = root.clone();
+        while deep.as_os_str().len() <= MAX_PATH + 40 {
+            deep.push("a-rather-long-folder-name");
+        }
+        fs::create_dir_all(long_path(&deep)).unwrap();
+        fs::write(long_path(&deep.join("file.txt")), "x").unwrap();
+
+        let entries =
+            list_files_in_directory(&long_path(&deep), true, &AtomicBool::new(false)).unwrap();
+        let names: Vec<String> = entries.iter().map(|(path, _)| display_path(path)).collect();
+        assert_eq!(names, [display_path(&deep.join("file.txt"))]);
+        assert_eq!(entries[0].1.size, Some(1));
+        fs::remove_dir_all(long_path(&root)).unwrap();
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn long_path_prefixes_only_long_absolute_paths() {
+        let long = format!(r"C:\{}", "x".repeat(MAX_PATH));
+        assert_eq!(long_path(Path::new(r"C:\short")), PathBuf::from(r"C:\short"));
+        assert_eq!(long_path(Path::new(&long)), PathBuf::from(format!(r"\\?\{}", long)));
+        let share = format!(r"\\server\share\{}", "x".repeat(MAX_PATH));
+        assert_eq!(
+            long_path(Path::new(&share)),
+            PathBuf::from(format!(r"\\?\UNC\{}", &share[2..]))
+        );
+        // Prefixed paths that got short again lose the prefix
+        assert_eq!(long_path(Path::new(r"\\?\C:\short")), PathBuf::from(r"C:\short"));
+        // `..` would not be resolved behind the prefix
+        let up = format!(r"C:\{}\..\b", "x".repeat(MAX_PATH));
+        assert_eq!(long_path(Path::new(&up)), PathBuf::from(&up));
+    }
+
+    #[test]
+    fn natural_cmp_orders_digit_runs_by_value_and_ignores_case() {
+        use std::cmp::Ordering;
+        assert_eq!(natural_cmp("img2", "img10"), Ordering::Less);
+        assert_eq!(natural_cmp("Report", "report 2"), Ordering::Less);
+        assert_eq!(natural_cmp("b.txt", "A.txt"), Ordering::Greater);
+        // Equal by value and case-insensitively, yet still ordered
+        assert_ne!(natural_cmp("file007", "file7"), Ordering::Equal);
+        assert_ne!(natural_cmp("a", "A"), Ordering::Equal);
+        let long = "9".repeat(40);
+        assert_eq!(natural_cmp(&format!("v{}", long), &format!("v1{}", long)), Ordering::Less);
+    }
+
+    /// `entries` sorted by `key` in each direction, for the metadata `meta` gives them.
+    fn sorted(
+        entries: &[&str],
+        meta: impl Fn(&str) -> EntryMeta,
+        key: SortKey,
+    ) -> [Vec<PathBuf>; 2] {
+        let meta: HashMap<_, _> =
+            entries.iter().map(|name| (PathBuf::from(name), meta(name))).collect();
+        [true, false].map(|ascending| {
+            let mut paths = entries.iter().map(PathBuf::from).collect();
+            sort_entries(&mut paths, &meta, key, ascending);
+            paths
+        })
+    }
+
+    #[test]
+    fn sort_entries_pins_the_parent_and_puts_folders_first_either_way() {
+        let dir = |name: &str| EntryMeta { is_dir: name.starts_with("dir"), ..EntryMeta::UNKNOWN };
+        let entries = ["b.txt", "dir_b", "..", "a.txt", "dir_a"];
+        let [ascending, descending] = sorted(&entries, dir, SortKey::Name);
+        assert_eq!(ascending, ["..", "dir_a", "dir_b", "a.txt", "b.txt"].map(PathBuf::from));
+        assert_eq!(descending, ["..", "dir_b", "dir_a", "b.txt", "a.txt"].map(PathBuf::from));
+    }
+
+    #[test]
+    fn sort_entries_puts_unreadable_sizes_last_either_way() {
+        let size = |name: &str| EntryMeta {
+            size: name.strip_suffix(".bin").and_then(|size| size.parse().ok()),
+            ..EntryMeta::UNKNOWN
+        };
+        let entries = ["unreadable", "20.bin", "3.bin"];
+        let [ascending, descending] = sorted(&entries, size, SortKey::Size);
+        assert_eq!(ascending, ["3.bin", "20.bin", "unreadable"].map(PathBuf::from));
+        assert_eq!(descending, ["20.bin", "3.bin", "unreadable"].map(PathBuf::from));
+    }
+
+    #[test]
+    fn sort_entries_keeps_folders_grouped_under_natural_order() {
+        let dir = |name: &str| EntryMeta { is_dir: name.starts_with("dir"), ..EntryMeta::UNKNOWN };
+        let [ascending, _] = sorted(&["a10.txt", "dir10", "a1.txt", "dir2"], dir, SortKey::Name);
+        assert_eq!(ascending, ["dir2", "dir10", "a1.txt", "a10.txt"].map(PathBuf::from));
+    }
+
+    #[test]
+    fn sibling_folders_lists_only_folders_in_natural_order() {
+        let root = temp_dir("siblings");
+        for dir in ["dir10", "dir2", "Dir3"] {
+            fs::create_dir(root.join(dir)).unwrap();
+        }
+        fs::write(root.join("dir1.txt"), "x").unwrap();
+        let siblings = sibling_folders(&root.join("dir2"));
+        assert_eq!(siblings, ["dir2", "Dir3", "dir10"].map(|name| root.join(name)));
+        assert_eq!(sibling_folders(Path::new("/")), Vec::<PathBuf>::new());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn is_hidden_spots_dotfiles_on_unix() {
+        assert!(!is_hidden(Path::new("/home/me/notes.txt"), None));
+        assert_eq!(is_hidden(Path::new("/home/me/.bashrc"), None), cfg!(not(windows)));
+        // Only the name counts, not a dot further up the path
+        assert!(!is_hidden(Path::new("/home/.me/notes.txt"), None));
+    }
+
+    #[test]
+    fn list_files_in_directory_leaves_out_hidden_entries_unless_asked() {
+        let dir = temp_dir("list-hidden");
+        fs::write(dir.join("visible.txt"), "x").unwrap();
+        fs::write(dir.join(".hidden"), "x").unwrap();
+        fs::create_dir(dir.join("sub")).unwrap();
+        let cancel = AtomicBool::new(false);
+        let names = |show_hidden| {
+            let listing = list_files_in_directory(&dir, show_hidden, &cancel).unwrap();
+            let mut names: Vec<_> = listing
+                .iter()
+                .map(|(path, meta)| (path.file_name().unwrap().to_owned(), meta.is_dir))
+                .collect();
+            names.sort();
+            names
+        };
+        let all = [(".hidden".into(), false), ("sub".into(), true), ("visible.txt".into(), false)];
+        if cfg!(windows) {
+            assert_eq!(names(false), all);
+        } else {
+            assert_eq!(names(false), all[1..]);
+        }
+        assert_eq!(names(true), all);
+
+        cancel.store(true, Ordering::Relaxed);
+        let err = list_files_in_directory(&dir, true, &cancel).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Interrupted);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn resolve_entry_handles_the_parent_relative_and_absolute_entries() {
+        let dir = Path::new("/home/me");
+        assert_eq!(resolve_entry(dir, Path::new("..")), Path::new("/home"));
+        assert_eq!(resolve_entry(Path::new("/"), Path::new("..")), Path::new("/"));
+        assert_eq!(resolve_entry(dir, Path::new("notes.txt")), Path::new("/home/me/notes.txt"));
+        assert_eq!(resolve_entry(dir, Path::new("/etc/hosts")), Path::new("/etc/hosts"));
+    }
+
+    #[test]
+    fn expand_path_resolves_home_relative_paths_and_variables() {
+        let base = fs::canonicalize(temp_dir("expand-path")).unwrap();
+        fs::create_dir(base.join("sub")).unwrap();
+        assert_eq!(expand_path("  sub ", &base), Ok(base.join("sub")));
+        assert!(expand_path("missing", &base).unwrap_err().contains("No such file or folder"));
+        assert!(expand_path("", &base).is_err());
+
+        if let Some(home) = home_dir().filter(|home| home.exists()) {
+            assert_eq!(expand_path("~", &base), Ok(home.clone()));
+            let var = if cfg!(windows) { "USERPROFILE" } else { "HOME" };
+            assert_eq!(expand_path(&format!("${}", var), &base), Ok(home.clone()));
+            assert_eq!(expand_path(&format!("${{{}}}", var), &base), Ok(home));
+        }
+        let err = expand_path("$FEX_TEST_UNSET_VAR/sub", &base).unwrap_err();
+        assert!(err.ends_with("(FEX_TEST_UNSET_VAR not set)"), "{}", err);
+        fs::remove_dir_all(&base).unwrap();
+    }
+
+    #[test]
+    fn parse_mounts_skips_pseudo_filesystems_and_unescapes_names() {
+        let mounts = "sysfs /sys sysfs rw 0 0\n\
+                      /dev/sda1 / ext4 rw 0 0\n\
+                      tmpfs /run tmpfs rw 0 0\n\
+                      /dev/sdb1 /media/USB\\040Stick vfat rw 0 0\n\
+                      truncated-line\n";
+        assert_eq!(parse_mounts(mounts), [PathBuf::from("/"), PathBuf::from("/media/USB Stick")]);
+    }
+
+    #[test]
+    fn unescape_mount_point_decodes_octal_escapes_only() {
+        assert_eq!(unescape_mount_point("/mnt/a\\011b\\134c"), "/mnt/a\tb\\c");
+        // Not three octal digits, so left as written
+        assert_eq!(unescape_mount_point("/mnt/x\\09"), "/mnt/x\\09");
+        assert_eq!(unescape_mount_point("/mnt/end\\"), "/mnt/end\\");
+    }
+
+    #[test]
+    fn expand_env_vars_keeps_unset_references_as_typed() {
+        let lookup = |name: &str| (name == "HOME").then(|| "/home/me".to_string());
+        let (text, unset) = expand_env_vars("$HOME/${HOME}/$NOPE/x", lookup);
+        assert_eq!(text, "/home/me//home/me/$NOPE/x");
+        assert_eq!(unset, ["NOPE"]);
+        // Lone markers and an unclosed brace are plain text
+        assert_eq!(expand_env_vars("a $ b ${HOME", lookup).0, "a $ b ${HOME");
+        assert_eq!(expand_env_vars("$Recycle.Bin", lookup).0, "$Recycle.Bin");
+    }
+
+    #[test]
+    fn expand_env_vars_reads_percent_references_only_on_windows() {
+        let lookup = |name: &str| (name == "APPDATA").then(|| r"C:\Users\me".to_string());
+        let expected = if cfg!(windows) { r"C:\Users\me\fex 100%" } else { r"%APPDATA%\fex 100%" };
+        assert_eq!(expand_env_vars(r"%APPDATA%\fex 100%", lookup).0, expected);
+    }
+
+    #[test]
+    fn unique_name_numbers_copies_before_the_extension() {
+        let dir = temp_dir("unique-name");
+        let name = std::ffi::OsStr::new("report.txt");
+        assert_eq!(unique_name(&dir, name, &HashSet::new()), dir.join("report.txt"));
+        fs::write(dir.join("report.txt"), "x").unwrap();
+        assert_eq!(unique_name(&dir, name, &HashSet::new()), dir.join("report (copy).txt"));
+        fs::write(dir.join("report (copy).txt"), "x").unwrap();
+        assert_eq!(unique_name(&dir, name, &HashSet::new()), dir.join("report (copy 2).txt"));
+        fs::create_dir(dir.join("notes")).unwrap();
+        let notes = std::ffi::OsStr::new("notes");
+        assert_eq!(unique_name(&dir, notes, &HashSet::new()), dir.join("notes (copy)"));
+        let reserved = HashSet::from([dir.join("notes (copy)")]);
+        assert_eq!(unique_name(&dir, notes, &reserved), dir.join("notes (copy 2)"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn percent_decode_decodes_escapes_and_rejects_bad_ones() {
+        let decoded = percent_decode("/home/me/My%20Files/%C3%A9t%C3%A9");
+        assert_eq!(decoded.as_deref(), Some("/home/me/My Files/été"));
+        assert_eq!(percent_decode("/no/escapes").as_deref(), Some("/no/escapes"));
+        assert_eq!(percent_decode("/bad/%zz"), None);
+        assert_eq!(percent_decode("/cut/short%2"), None);
+        assert_eq!(percent_decode("/not/utf8/%FF"), None);
+    }
+
+    #[test]
+    fn parse_clipboard_paths_reads_plain_paths_and_file_uris() {
+        let text = "# copied from a file manager\n\
+                    file:///home/me/a%20b.txt\n\
+                    file://localhost/home/me/c.txt\n\
+                    \"/home/me/quoted.txt\"\n\
+                    https://example.com/page\n\
+                    \n\
+                    /home/me/plain.txt\n";
+        assert_eq!(
+            parse_clipboard_paths(text),
+            ["/home/me/a b.txt", "/home/me/c.txt", "/home/me/quoted.txt", "/home/me/plain.txt"]
+                .map(PathBuf::from)
+        );
+    }
+
+    /// Runs every step of `steps` in one go.
+    fn run_paste(steps: &[PasteStep], progress: &mut PasteProgress) {
+        assert_eq!(paste_batch(steps, progress, &AtomicBool::new(false)), steps.len());
+    }
+
+    #[test]
+    fn moving_by_copy_copies_the_tree_then_removes_the_original() {
+        let root = temp_dir("move-by-copy");
+        let (source, dest) = (root.join("source"), root.join("dest"));
+        fs::create_dir_all(source.join("sub")).unwrap();
+        fs::write(source.join("sub").join("file.txt"), "hello").unwrap();
+
+        let mut progress = PasteProgress::default();
+        run_paste(&plan_copy(0, &source, &dest, ClipOp::Cut).unwrap(), &mut progress);
+        assert_eq!((progress.pasted, progress.failed.len()), (1, 0));
+        assert!(!source.exists());
+        assert_eq!(fs::read_to_string(dest.join("sub").join("file.txt")).unwrap(), "hello");
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn crosses_devices_spots_a_rename_refused_across_filesystems() {
+        let code = if cfg!(windows) { 17 } else { 18 };
+        assert!(crosses_devices(&io::Error::from_raw_os_error(code)));
+        assert!(crosses_devices(&io::Error::from(io::ErrorKind::CrossesDevices)));
+        assert!(!crosses_devices(&io::Error::from(io::ErrorKind::PermissionDenied)));
+    }
+
+    #[test]
+    fn a_cross_device_move_reports_progress_step_by_step() {
+        let root = temp_dir("move-progress");
+        let (source, dest) = (root.join("source"), root.join("dest"));
+        fs::create_dir(&source).unwrap();
+        for name in ["a.txt", "b.txt", "c.txt"] {
+            fs::write(source.join(name), name).unwrap();
+        }
+
+        // What plan_paste falls back to when the rename crosses filesystems
+        let steps = plan_copy(0, &source, &dest, ClipOp::Cut).unwrap();
+        assert_eq!(steps.len(), 5);  // The folder, three files, then removing the original
+        let (mut progress, cancel) = (PasteProgress::default(), AtomicBool::new(false));
+        let mut done = 0;
+        while done < steps.len() {
+            done += paste_batch(&steps[done..(done + 2).min(steps.len())], &mut progress, &cancel);
+            assert_eq!(progress.pasted, usize::from(done == steps.len()));
+        }
+        assert!(!source.exists());
+        assert_eq!(fs::read_to_string(dest.join("b.txt")).unwrap(), "b.txt");
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn a_failed_move_removes_the_partial_copy_and_keeps_the_original() {
+        let root = temp_dir("move-rollback");
+        let (source, dest) = (root.join("source"), root.join("dest"));
+        fs::create_dir(&source).unwrap();
+        fs::write(source.join("a.txt"), "a").unwrap();
+        fs::write(source.join("b.txt"), "b").unwrap();
+
+        let steps = plan_copy(0, &source, &dest, ClipOp::Cut).unwrap();
+        // Gone between planning and copying, so the copy fails partway
+        fs::remove_file(source.join("b.txt")).unwrap();
+        let mut progress = PasteProgress::default();
+        run_paste(&steps, &mut progress);
+        assert_eq!(progress.pasted, 0);
+        assert!(progress.failed.contains_key(&0));
+        assert!(!dest.exists());
+        assert_eq!(fs::read_to_string(source.join("a.txt")).unwrap(), "a");
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn plan_paste_renames_clashes_and_refuses_pasting_a_folder_into_itself() {
+        let root = temp_dir("plan-paste");
+        let (first, second, dest) = (root.join("one"), root.join("two"), root.join("dest"));
+        for dir in [&first, &second, &dest] {
+            fs::create_dir(dir).unwrap();
+        }
+        fs::write(first.join("notes.txt"), "one").unwrap();
+        fs::write(second.join("notes.txt"), "two").unwrap();
+        fs::write(dest.join("notes.txt"), "dest").unwrap();
+
+        let sources = [first.join("notes.txt"), second.join("notes.txt"), root.clone()];
+        let cancel = AtomicBool::new(false);
+        let mut plan = plan_paste(&sources, &dest, ClipOp::Copy, &cancel).unwrap();
+        assert_eq!(plan.progress.failed.keys().collect::<Vec<_>>(), [&2]);
+        run_paste(&plan.steps, &mut plan.progress);
+        assert_eq!(plan.progress.pasted, 2);
+        assert_eq!(fs::read_to_string(dest.join("notes.txt")).unwrap(), "dest");
+        assert_eq!(fs::read_to_string(dest.join("notes (copy).txt")).unwrap(), "one");
+        assert_eq!(fs::read_to_string(dest.join("notes (copy 2).txt")).unwrap(), "two");
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn a_cancelled_paste_undoes_the_entry_under_way() {
+        let root = temp_dir("paste-cancel");
+        let (source, dest) = (root.join("source"), root.join("dest"));
+        fs::create_dir(&source).unwrap();
+        fs::write(source.join("a.txt"), "a").unwrap();
+
+        let steps = plan_copy(0, &source, &dest, ClipOp::Copy).unwrap();
+        let mut progress = PasteProgress::default();
+        let cancel = AtomicBool::new(false);
+        assert_eq!(paste_batch(&steps[..1], &mut progress, &cancel), 1);
+        assert!(dest.exists());
+        cancel.store(true, Ordering::Relaxed);
+        assert_eq!(paste_batch(&steps[1..], &mut progress, &cancel), steps.len() - 1);
+        assert_eq!(progress.pasted, 0);
+        assert!(!dest.exists());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn copying_recreates_symlinks_instead_of_following_them() {
+        use std::os::unix::fs::symlink;
+        let root = temp_dir("copy-links");
+        let (source, dest) = (root.join("source"), root.join("dest"));
+        fs::create_dir_all(root.join("elsewhere")).unwrap();
+        fs::write(root.join("elsewhere").join("big.bin"), "data").unwrap();
+        fs::create_dir(&source).unwrap();
+        symlink(root.join("elsewhere"), source.join("folder-link")).unwrap();
+        symlink("missing.txt", source.join("dangling")).unwrap();
+
+        let mut progress = PasteProgress::default();
+        run_paste(&plan_copy(0, &source, &dest, ClipOp::Copy).unwrap(), &mut progress);
+        assert_eq!(progress.pasted, 1);
+        let link = fs::symlink_metadata(dest.join("folder-link")).unwrap();
+        assert!(link.file_type().is_symlink());
+        assert_eq!(fs::read_link(dest.join("folder-link")).unwrap(), root.join("elsewhere"));
+        assert_eq!(fs::read_link(dest.join("dangling")).unwrap(), Path::new("missing.txt"));
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,158 @@
+//! Shell-style wildcards for the filter box: `*` matches any run of characters, `?`
+//! any one, and `[abc]`, `[a-z]` or `[!abc]` one character in (or not in) the set. To
+//! match a wildcard character itself, put it in brackets, as in `[*]`. Matching is
+//! case-insensitive, like the plain filter.
+
+#[derive(Debug, Clone)]
+enum Token {
+    Char(char),
+    AnyChar,
+    AnyRun,
+    Set { negated: bool, ranges: Vec<(char, char)> },
+}
+
+impl Token {
+    /// Whether this token matches `c` on its own; `AnyRun` is handled by the caller.
+    fn accepts(&self, c: char) -> bool {
+        match self {
+            Token::Char(expected) => *expected == c,
+            Token::AnyChar => true,
+            Token::AnyRun => false,
+            Token::Set { negated, ranges } => {
+                ranges.iter().any(|(low, high)| (*low..=*high).contains(&c)) != *negated
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Pattern(Vec<Token>);
+
+impl Pattern {
+    /// Compiles `pattern`, or says what's wrong with it.
+    pub fn new(pattern: &str) -> Result<Pattern, String> {
+        let chars: Vec<char> = pattern.chars().flat_map(char::to_lowercase).collect();
+        let mut tokens = Vec::new();
+        let mut i = 0;
+        while i < chars.len() {
+            match chars[i] {
+                '*' => {
+                    // A run of stars means the same as one
+                    if !matches!(tokens.last(), Some(Token::AnyRun)) {
+                        tokens.push(Token::AnyRun);
+                    }
+                }
+                '?' => tokens.push(Token::AnyChar),
+                '[' => {
+                    let start = i;
+                    i += 1;
+                    let negated = matches!(chars.get(i), Some('!' | '^'));
+                    if negated {
+                        i += 1;
+                    }
+                    let mut ranges = Vec::new();
+                    // A `]` straight after the opening bracket is part of the set
+                    let mut first = true;
+                    loop {
+                        let Some(&low) = chars.get(i) else {
+                            return Err(format!(
+                                "Unclosed `[` at character {} of the pattern",
+                                start + 1
+                            ));
+                        };
+                        if low == ']' && !first {
+                            break;
+                        }
+                        first = false;
+                        match (chars.get(i + 1), chars.get(i + 2)) {
+                            (Some('-'), Some(&high)) if high != ']' => {
+                                if high < low {
+                                    return Err(format!(
+                                        "The range `{}-{}` runs backwards",
+                                        low, high
+                                    ));
+                                }
+                                ranges.push((low, high));
+                                i += 3;
+                            }
+                            _ => {
+                                ranges.push((low, low));
+                                i += 1;
+                            }
+                        }
+                    }
+                    tokens.push(Token::Set { negated, ranges });
+                }
+                c => tokens.push(Token::Char(c)),
+            }
+            i += 1;
+        }
+        Ok(Pattern(tokens))
+    }
+
+    /// Whether the whole of `name` matches the pattern.
+    pub fn matches(&self, name: &str) -> bool {
+        let name: Vec<char> = name.chars().flat_map(char::to_lowercase).collect();
+        let (mut t, mut n) = (0, 0);
+        // The last `*` seen, and where in `name` its run currently ends
+        let mut backtrack = None;
+        while n < name.len() {
+            match self.0.get(t) {
+                Some(Token::AnyRun) => {
+                    backtrack = Some((t, n));
+                    t += 1;
+                }
+                Some(token) if token.accepts(name[n]) => {
+                    t += 1;
+                    n += 1;
+                }
+                // No match here; let the last `*` swallow one more character
+                _ => match backtrack {
+                    Some((star, end)) => {
+                        backtrack = Some((star, end + 1));
+                        t = star + 1;
+                        n = end + 1;
+                    }
+                    None => return false,
+                },
+            }
+        }
+        self.0[t..].iter().all(|token| matches!(token, Token::AnyRun))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, name: &str) -> bool {
+        Pattern::new(pattern).unwrap().matches(name)
+    }
+
+    #[test]
+    fn wildcards_match_runs_and_single_characters() {
+        assert!(matches("*.rs", "main.rs"));
+        assert!(matches("*.RS", "Main.rs"));
+        assert!(!matches("*.rs", "main.rs.bak"));
+        assert!(matches("a*b*c", "aXbYbZc"));
+        assert!(matches("file?.txt", "file1.txt"));
+        assert!(!matches("file?.txt", "file.txt"));
+        assert!(matches("**", ""));
+    }
+
+    #[test]
+    fn sets_match_listed_ranged_and_negated_characters() {
+        assert!(matches("[abc]*", "beta"));
+        assert!(matches("img[0-9].png", "img7.png"));
+        assert!(!matches("img[!0-9].png", "img7.png"));
+        assert!(matches("[]]", "]"));
+        assert!(matches("[*]", "*"));
+        assert!(!matches("[*]", "x"));
+    }
+
+    #[test]
+    fn bad_patterns_say_what_is_wrong() {
+        assert!(Pattern::new("[abc").unwrap_err().contains("Unclosed"));
+        assert!(Pattern::new("[z-a]").unwrap_err().contains("backwards"));
+    }
+}
@@ -0,0 +1,368 @@
+//! Handing entries over to other programs. The explorer goes through the `Launcher`
+//! trait, so anything that would start a process can be swapped out. Also works out
+//! what kind of file an entry is and which apps can open it.
+
+use std::{
+    ffi::OsString,
+    io,
+    path::{Path, PathBuf},
+    process::Command as ProcessCommand,
+};
+// Only the desktop database lookups read files
+#[cfg(all(unix, not(target_os = "macos")))]
+use std::fs;
+
+pub trait Launcher {
+    /// Opens `path` with the app the system associates with it.
+    fn open(&self, path: &Path) -> io::Result<()>;
+
+    /// Shows `path` in the system file manager, selected where the platform allows.
+    /// Unlike `open`, this locates the entry rather than launching it.
+    fn reveal(&self, path: &Path) -> io::Result<()>;
+
+    /// Opens `a` and `b` side by side in a diff tool. Fails with `NotFound` when no
+    /// diff tool is installed.
+    fn compare(&self, a: &Path, b: &Path) -> io::Result<()>;
+}
+
+/// Starts the platform's own tools: `start`/`explorer` on Windows, `open` on macOS
+/// and `xdg-open` elsewhere.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemLauncher;
+
+impl Default for Box<dyn Launcher> {
+    fn default() -> Self {
+        Box::new(SystemLauncher)
+    }
+}
+
+impl Launcher for SystemLauncher {
+    fn open(&self, file_path: &Path) -> io::Result<()> {
+        #[cfg(windows)]
+        {
+            let valid_path = file_path.to_str().ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, "path is not valid Unicode")
+            })?;
+            ProcessCommand::new("cmd")
+                .args(["/C", "start", valid_path])
+                .spawn()?;
+        }
+        #[cfg(target_os = "macos")]
+        ProcessCommand::new("open").arg(file_path).spawn()?;
+        #[cfg(not(any(windows, target_os = "macos")))]
+        ProcessCommand::new("xdg-open").arg(file_path).spawn()?;
+        Ok(())
+    }
+
+    fn reveal(&self, path: &Path) -> io::Result<()> {
+        #[cfg(windows)]
+        {
+            use std::os::windows::process::CommandExt;
+            // explorer only takes `/select,` and the path as one argument, with just the
+            // path quoted, which `arg` would quote as a whole
+            ProcessCommand::new("explorer")
+                .raw_arg(format!("/select,\"{}\"", path.display()))
+                .spawn()?;
+        }
+        #[cfg(target_os = "macos")]
+        ProcessCommand::new("open").arg("-R").arg(path).spawn()?;
+        #[cfg(not(any(windows, target_os = "macos")))]
+        {
+            // xdg-open has no way to select an entry, so open the folder holding it
+            ProcessCommand::new("xdg-open")
+                .arg(path.parent().unwrap_or(path))
+                .spawn()?;
+        }
+        Ok(())
+    }
+
+    fn compare(&self, a: &Path, b: &Path) -> io::Result<()> {
+        let (tool, args) = DIFF_TOOLS
+            .iter()
+            .find_map(|(name, args)| find_on_path(name).map(|tool| (tool, *args)))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no diff tool installed"))?;
+        ProcessCommand::new(tool).args(args).arg(a).arg(b).spawn()?;
+        Ok(())
+    }
+}
+
+// Diff tools tried in turn, with the arguments that go before the two paths
+#[cfg(windows)]
+const DIFF_TOOLS: [(&str, &[&str]); 3] =
+    [("WinMergeU", &[]), ("meld", &[]), ("code", &["--diff"])];
+#[cfg(target_os = "macos")]
+const DIFF_TOOLS: [(&str, &[&str]); 3] =
+    [("opendiff", &[]), ("meld", &[]), ("code", &["--diff"])];
+#[cfg(not(any(windows, target_os = "macos")))]
+const DIFF_TOOLS: [(&str, &[&str]); 4] =
+    [("meld", &[]), ("kdiff3", &[]), ("kompare", &[]), ("code", &["--diff"])];
+
+/// Where `program` would be found on `PATH`, trying the `PATHEXT` extensions on
+/// Windows.
+fn find_on_path(program: &str) -> Option<PathBuf> {
+    let extensions: Vec<String> = if cfg!(windows) {
+        std::env::var("PATHEXT")
+            .unwrap_or_else(|_| ".EXE;.CMD;.BAT".to_string())
+            .split(';')
+            .map(str::to_string)
+            .collect()
+    } else {
+        vec![String::new()]
+    };
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path).find_map(|dir| {
+        extensions
+            .iter()
+            .map(|ext| dir.join(format!("{}{}", program, ext)))
+            .find(|candidate| candidate.is_file())
+    })
+}
+
+/// A command of the user's own offered for the entries it applies to, set in the
+/// settings as `custom_action=label|applies to|command`, e.g.
+/// `custom_action=Convert to PNG|svg,webp|convert {path} {dir}/converted.png`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomAction {
+    pub label: String,
+    // Lowercase extensions, with "dir" standing for folders; empty for every entry
+    pub applies_to: Vec<String>,
+    // Split into words on whitespace, each one argument, with `{path}` and `{dir}`
+    // replaced; no shell sees it
+    pub command: String,
+}
+
+impl CustomAction {
+    /// Reads the `label|applies to|command` form the settings store it in. The label
+    /// and the command can't be empty; "applies to" is a comma-separated list.
+    pub fn parse(value: &str) -> Option<CustomAction> {
+        let mut fields = value.splitn(3, '|').map(str::trim);
+        let (label, applies_to, command) = (fields.next()?, fields.next()?, fields.next()?);
+        if label.is_empty() || command.is_empty() {
+            return None;
+        }
+        let applies_to = applies_to
+            .split(',')
+            .map(|kind| kind.trim().trim_start_matches('.').to_lowercase())
+            .filter(|kind| !kind.is_empty())
+            .collect();
+        Some(CustomAction { label: label.to_string(), applies_to, command: command.to_string() })
+    }
+
+    /// The form `parse` reads.
+    pub fn to_setting(&self) -> String {
+        format!("{}|{}|{}", self.label, self.applies_to.join(","), self.command)
+    }
+
+    /// Whether to offer the action for `path`, a folder when `is_dir`.
+    pub fn applies_to(&self, path: &Path, is_dir: bool) -> bool {
+        if self.applies_to.is_empty() {
+            return true;
+        }
+        if is_dir {
+            return self.applies_to.iter().any(|kind| kind == "dir");
+        }
+        let extension = path.extension().map(|ext| ext.to_string_lossy().to_lowercase());
+        extension.is_some_and(|ext| self.applies_to.contains(&ext))
+    }
+
+    /// The program and arguments to run for `path`: `{path}` becomes the entry and
+    /// `{dir}` the folder holding it.
+    pub fn command_line(&self, path: &Path) -> Vec<OsString> {
+        let dir = path.parent().unwrap_or(path);
+        self.command
+            .split_whitespace()
+            .map(|word| {
+                let mut arg = OsString::new();
+                let mut rest = word;
+                loop {
+                    let next = [("{path}", path), ("{dir}", dir)]
+                        .into_iter()
+                        .filter_map(|(placeholder, value)| {
+                            rest.find(placeholder).map(|at| (at, placeholder, value))
+                        })
+                        .min_by_key(|(at, _, _)| *at);
+                    let Some((at, placeholder, value)) = next else {
+                        arg.push(rest);
+                        break arg;
+                    };
+                    arg.push(&rest[..at]);
+                    arg.push(value);
+                    rest = &rest[at + placeholder.len()..];
+                }
+            })
+            .collect()
+    }
+}
+
+/// Runs `command` (program first) in `dir` and waits for it, so call it off the UI
+/// thread. Gives what it printed on success, or why it failed: its error output, or
+/// its exit status when it printed nothing.
+pub fn run_custom_action(command: &[OsString], dir: &Path) -> Result<String, String> {
+    let (program, args) = command.split_first().ok_or("the command is empty")?;
+    let output = ProcessCommand::new(program)
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .map_err(|err| format!("cannot run {}: {}", program.to_string_lossy(), err))?;
+    let printed = |bytes: &[u8]| String::from_utf8_lossy(bytes).trim().to_string();
+    if output.status.success() {
+        return Ok(printed(&output.stdout));
+    }
+    let stderr = printed(&output.stderr);
+    Err(if stderr.is_empty() { output.status.to_string() } else { stderr })
+}
+
+/// What kind of file an entry is, and the apps that open that kind, default first.
+#[derive(Debug, Clone, Default)]
+pub struct FileType {
+    pub mime: Option<String>,
+    pub apps: Vec<String>,
+}
+
+/// Looks up `path`'s MIME type and the apps registered for it. On Linux this asks
+/// `xdg-mime` and the desktop database, which takes a subprocess or two, so call it off
+/// the UI thread. Elsewhere, and when `xdg-mime` isn't installed, the type is guessed
+/// from the extension and no apps are known.
+pub fn file_type(path: &Path) -> FileType {
+    #[cfg(all(unix, not(target_os = "macos")))]
+    if let Some(mime) = xdg_mime(&["query", "filetype"], path.as_os_str()) {
+        let default = xdg_mime(&["query", "default"], mime.as_ref());
+        return FileType { apps: registered_apps(&mime, default), mime: Some(mime) };
+    }
+    FileType { mime: mime_from_extension(path).map(str::to_string), apps: Vec::new() }
+}
+
+/// Runs `xdg-mime` with `args` and `last`, returning its first line of output.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn xdg_mime(args: &[&str], last: &std::ffi::OsStr) -> Option<String> {
+    let output = ProcessCommand::new("xdg-mime").args(args).arg(last).output().ok()?;
+    let text = String::from_utf8(output.stdout).ok()?;
+    let line = text.lines().next()?.trim();
+    (output.status.success() && !line.is_empty()).then(|| line.to_string())
+}
+
+/// Folders desktop entries are installed in, most specific first.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn application_dirs() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = dirs::data_dir().into_iter().collect();
+    let system = std::env::var("XDG_DATA_DIRS")
+        .unwrap_or_else(|_| "/usr/local/share:/usr/share".to_string());
+    dirs.extend(system.split(':').filter(|dir| !dir.is_empty()).map(PathBuf::from));
+    dirs.into_iter().map(|dir| dir.join("applications")).collect()
+}
+
+/// The names of the apps the desktop database lists for `mime`, with `default` (a
+/// desktop entry id) moved to the front.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn registered_apps(mime: &str, default: Option<String>) -> Vec<String> {
+    let dirs = application_dirs();
+    let mut ids: Vec<String> = default.into_iter().collect();
+    for dir in &dirs {
+        let Ok(cache) = fs::read_to_string(dir.join("mimeinfo.cache")) else {
+            continue;
+        };
+        let listed = cache
+            .lines()
+            .filter_map(|line| line.split_once('='))
+            .filter(|(key, _)| *key == mime)
+            .flat_map(|(_, ids)| ids.split(';'));
+        for id in listed.filter(|id| !id.is_empty()) {
+            if !ids.iter().any(|known| known == id) {
+                ids.push(id.to_string());
+            }
+        }
+    }
+    ids.iter().map(|id| app_name(&dirs, id)).collect()
+}
+
+/// The `Name=` a desktop entry gives its app, or the entry id without `.desktop` when
+/// the file can't be found.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn app_name(dirs: &[PathBuf], id: &str) -> String {
+    dirs.iter()
+        .find_map(|dir| fs::read_to_string(dir.join(id)).ok())
+        .and_then(|entry| {
+            entry
+                .lines()
+                .find_map(|line| line.strip_prefix("Name="))
+                .map(|name| name.trim().to_string())
+        })
+        .unwrap_or_else(|| id.trim_end_matches(".desktop").to_string())
+}
+
+/// A MIME type for common extensions (case-insensitive), for when the system can't
+/// be asked.
+pub fn mime_from_extension(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_string_lossy().to_lowercase();
+    let mime = match extension.as_str() {
+        "txt" | "log" => "text/plain",
+        "md" => "text/markdown",
+        "csv" => "text/csv",
+        "html" | "htm" => "text/html",
+        "css" => "text/css",
+        "js" => "text/javascript",
+        "json" => "application/json",
+        "xml" => "application/xml",
+        "toml" => "application/toml",
+        "yaml" | "yml" => "application/yaml",
+        "rs" => "text/rust",
+        "py" => "text/x-python",
+        "sh" => "application/x-shellscript",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "gz" | "tgz" => "application/gzip",
+        "tar" => "application/x-tar",
+        "7z" => "application/x-7z-compressed",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "bmp" => "image/bmp",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "ico" => "image/vnd.microsoft.icon",
+        "tif" | "tiff" => "image/tiff",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "flac" => "audio/flac",
+        "ogg" | "opus" => "audio/ogg",
+        "mp4" => "video/mp4",
+        "mkv" => "video/x-matroska",
+        "webm" => "video/webm",
+        "mov" => "video/quicktime",
+        _ => return None,
+    };
+    Some(mime)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mime_from_extension_ignores_case_and_knows_nothing_of_odd_names() {
+        assert_eq!(mime_from_extension(Path::new("photo.JPG")), Some("image/jpeg"));
+        assert_eq!(mime_from_extension(Path::new("notes.md")), Some("text/markdown"));
+        assert_eq!(mime_from_extension(Path::new("Makefile")), None);
+        assert_eq!(mime_from_extension(Path::new("data.unknownext")), None);
+    }
+
+    #[test]
+    fn custom_actions_parse_apply_and_fill_in_the_path() {
+        let setting = "Convert to PNG | SVG, .webp | convert {path} {dir}/out.png";
+        let action = CustomAction::parse(setting).unwrap();
+        assert_eq!(action.applies_to, ["svg", "webp"]);
+        assert_eq!(CustomAction::parse(&action.to_setting()), Some(action.clone()));
+        assert!(action.applies_to(Path::new("/pics/logo.SVG"), false));
+        assert!(!action.applies_to(Path::new("/pics/logo.png"), false));
+        assert!(!action.applies_to(Path::new("/pics/icons.svg"), true));
+        assert_eq!(
+            action.command_line(Path::new("/my pics/logo.svg")),
+            ["convert", "/my pics/logo.svg", "/my pics/out.png"].map(OsString::from)
+        );
+
+        let everywhere = CustomAction::parse("Count||wc -l {path}").unwrap();
+        assert!(everywhere.applies_to(Path::new("/any"), true));
+        assert_eq!(CustomAction::parse("No command|txt|"), None);
+        assert_eq!(CustomAction::parse("Missing fields"), None);
+    }
+}
@@ -1,307 +1,5231 @@
 use iced::{
-    button, scrollable, Button, Column, Command, Element, Scrollable, Text,
-    Application, Settings, Length, Row, Space
+    button, container, scrollable, text_input, Align, Background, Button, Color, Column,
+    Command, Container, Element, Scrollable, Text, TextInput, Application, Settings, Length,
+    Row, Space, Subscription,
 };
+use iced_native::{event, keyboard, mouse, window, Event};
 use std::{
+    collections::{HashMap, HashSet},
     env,
     fs,
-    path::{Path, PathBuf},
-    process::Command as ProcessCommand,
-    time::{Instant, Duration},
+    io,
+    path::{Component, Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Instant, Duration, SystemTime},
 };
 
+mod diff;
+mod fs_ops;
+mod glob;
+mod launcher;
+
+use fs_ops::{
+    display_path, long_path, sort_entries, ClipOp, DeleteOutcome, EntryMeta, PastePlan,
+    PasteProgress, PasteStep, SortKey,
+};
+use launcher::Launcher;
+
 #[derive(Debug, Clone)]
 pub enum Message {
     FileClicked(PathBuf),
     Refresh,
     GoUp,
     DriveSelected(PathBuf),
+    ToggleFlatten,
+    FlattenLoaded(PathBuf, Vec<PathBuf>, HashMap<PathBuf, EntryMeta>),
+    FilesLoaded(PathBuf, Result<Vec<(PathBuf, EntryMeta)>, (io::ErrorKind, String)>),
+    PasteFromOS,
+    CycleParentEntryStyle,
+    ShowGoTo,
+    GoToChanged(String),
+    GoToSubmitted,
+    CloseDialogs,
+    DiscardAndLeave,
+    KeepEditing,
+    CloseRequested,
+    NavigateTo(PathBuf),
+    ToggleFolderCounts,
+    FolderCountsLoaded(PathBuf, Vec<(PathBuf, Option<usize>)>),
+    ToggleDirSizes,
+    DirSizeComputed(PathBuf, Option<DirSize>),
+    ShowSiblings,
+    SiblingsLoaded(PathBuf, Vec<PathBuf>),
+    AddressChanged(String),
+    AddressSubmitted,
+    SortBy(SortKey),
+    DismissError,
+    FilterChanged(String),
+    Back,
+    Forward,
+    NewFolder,
+    ChangeExtension,
+    ChangeExtensionChanged(String),
+    ApplyChangeExtension,
+    NewFolderNameChanged(String),
+    CreateFolder,
+    Delete(PathBuf),
+    ConfirmDelete,
+    DeletePlanned(Vec<PathBuf>),
+    DeleteProgress(Vec<(PathBuf, DeleteOutcome)>, HashSet<PathBuf>),
+    CancelDeleteJob,
+    StartRename(PathBuf),
+    RenameChanged(String),
+    CommitRename(String),
+    ToggleDrives,
+    GoHome,
+    SelectPrevious,
+    SelectNext,
+    ToggleCursorSelected,
+    OpenSelected,
+    ToggleHidden,
+    CopyPath(PathBuf),
+    DirectoryChanged(PathBuf, Option<watch::Changes>),
+    AddBookmark,
+    RemoveBookmark(PathBuf),
+    ShowProperties(PathBuf),
+    CopySelectionBreakdown,
+    FileTypeLoaded(PathBuf, launcher::FileType),
+    RunCustomAction(usize, PathBuf),  // Index into `config.custom_actions`
+    SetTag(PathBuf, Option<TagColor>),
+    CustomActionFinished(String, Result<String, String>),
+    CompareFiles(PathBuf, PathBuf),
+    DiffLoaded(PathBuf, PathBuf, Result<Vec<diff::Line>, String>),
+    ToggleTheme,
+    ModifiersChanged(keyboard::Modifiers),
+    CopySelected,
+    CutSelected,
+    Paste,
+    PasteTo(PathBuf),
+    PastePlanned(Result<PastePlan, String>),
+    PasteProgress(usize, PasteProgress),
+    CancelPasteJob,
+    PreviewLoaded(PathBuf, Option<(u32, u32)>),
+    ListScrolled,
+    ToggleFilterMode,
+    RevealInOsManager(PathBuf),
+    ToggleClickMode,
+    ToggleIconSet,
+    ToggleLinkTargetStyle,
+    ReloadConfig,
+    NewTab,
+    OpenSelectedInNewTab,
+    CloseTab(usize),
+    SelectTab(usize),
+    FileDropped(PathBuf),
+    ConfirmDrop,
+    TypeAhead(char),
+}
+
+/// A cached recursive folder size.
+#[derive(Debug, Clone)]
+pub struct DirSize {
+    size: u64,
+    computed_at: SystemTime,
+    dir_mtime: Option<SystemTime>,  // The folder's own mtime when we measured it
+    stale: bool,  // Shown dimmed while a recomputation is pending
+}
+
+/// Totals for a multi-selection, shown in the status bar.
+struct SelectionBreakdown {
+    count: usize,
+    bytes: u64,  // Files, plus the folders whose size is known so far
+    largest: Option<(PathBuf, u64)>,
+    unmeasured: usize,  // Selected folders whose size is still being worked out
+}
+
+impl SelectionBreakdown {
+    /// The breakdown as lines of text, for the clipboard.
+    fn report(&self) -> String {
+        let mut text = format!(
+            "Items: {}\nTotal: {} ({} bytes)\n",
+            self.count,
+            format_size(self.bytes),
+            self.bytes
+        );
+        if let Some((path, size)) = &self.largest {
+            text.push_str(&format!("Largest: {} ({})\n", display_path(path), format_size(*size)));
+        }
+        if self.unmeasured > 0 {
+            text.push_str(&format!("Not yet measured: {} folders\n", self.unmeasured));
+        }
+        text
+    }
+}
+
+// Cached folder sizes older than this are shown as possibly stale and recomputed, since
+// changes deep inside a tree don't bump the top folder's mtime
+const DIR_SIZE_MAX_AGE: Duration = Duration::from_secs(300);
+
+/// Totals for the status bar, covering exactly the entries listed (never `..`).
+#[derive(Debug, Clone, Copy, Default)]
+struct ListingSummary {
+    folders: usize,
+    files: usize,
+    bytes: u64,  // Regular files only; unreadable entries are left out
+}
+
+impl ListingSummary {
+    fn of(entries: &[PathBuf], meta: &HashMap<PathBuf, EntryMeta>) -> Self {
+        let mut summary = ListingSummary::default();
+        for entry in entries.iter().filter(|entry| *entry != Path::new("..")) {
+            match meta.get(entry) {
+                Some(meta) if meta.is_dir => summary.folders += 1,
+                // Still listed, so still counted, even if we can't tell its size
+                Some(meta) => {
+                    summary.files += 1;
+                    summary.bytes += meta.size.unwrap_or(0);
+                }
+                None => summary.files += 1,
+            }
+        }
+        summary
+    }
+}
+
+/// Startup options gathered in `main` before the window opens.
+struct Flags {
+    start_path: Option<PathBuf>,  // Directory given on the command line
+    #[cfg(unix)]
+    ipc_listener: Option<std::sync::Arc<std::os::unix::net::UnixListener>>,
+}
+
+/// How the `..` entry at the top of the list is presented.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum ParentEntryStyle {
+    #[default]
+    Parent,  // ".. (parent)"
+    UpRow,   // "⬆ Up"
+    Hidden,  // Rely on the Go Up button
+}
+
+impl ParentEntryStyle {
+    fn next(self) -> Self {
+        match self {
+            ParentEntryStyle::Parent => ParentEntryStyle::UpRow,
+            ParentEntryStyle::UpRow => ParentEntryStyle::Hidden,
+            ParentEntryStyle::Hidden => ParentEntryStyle::Parent,
+        }
+    }
+}
+
+/// The image shown in the preview pane and what has been read of it so far.
+struct Preview {
+    path: PathBuf,
+    loaded: bool,  // The background read has finished
+    dimensions: Option<(u32, u32)>,  // Width and height, if the header could be read
+}
+
+/// The in-app comparison of two files, used when no diff tool is installed.
+struct DiffView {
+    a: PathBuf,
+    b: PathBuf,
+    lines: Option<Result<Vec<diff::Line>, String>>,  // None until the background read ends
+    scroll: scrollable::State,
+    close_button: button::State,
+}
+
+/// How the filter box matches file names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum FilterMode {
+    #[default]
+    Contains,  // Case-insensitive substring
+    Glob,  // Wildcards, see `glob::Pattern`
+}
+
+/// The filter box's text along with the pattern compiled from it.
+///
+/// The filter only decides which rows are drawn: a tab's `files` stays the whole sorted
+/// listing, so typing never re-sorts, and the `..` and folders-first rules hold whatever
+/// the text. Filtering first and sorting only what matches gives the same rows, but
+/// `filter_order_benchmark` has it about five times slower per keystroke: in a release
+/// build, 1.2ms against 4.1ms for 10,000 entries and 130ms against 780ms for a million.
+/// The listing has to be sorted whole anyway, to show it before anything is typed.
+/// Hidden entries are another matter; they're dropped while listing, so the sort never
+/// sees them.
+#[derive(Debug, Clone, Default)]
+struct NameFilter {
+    text: String,
+    mode: FilterMode,
+    glob: Option<glob::Pattern>,  // Set in glob mode while the text compiles
+    error: Option<String>,  // Why it didn't, shown under the box
+}
+
+impl NameFilter {
+    /// Replaces the text and mode, compiling the text if it's a glob.
+    fn set(&mut self, text: String, mode: FilterMode) {
+        let compiled = match mode {
+            FilterMode::Glob if !text.is_empty() => Some(glob::Pattern::new(&text)),
+            _ => None,
+        };
+        (self.glob, self.error) = match compiled {
+            Some(Ok(glob)) => (Some(glob), None),
+            Some(Err(err)) => (None, Some(err)),
+            None => (None, None),
+        };
+        self.text = text;
+        self.mode = mode;
+    }
+
+    /// Whether `file` should be shown. The `..` entry always is, so you can still
+    /// back out, and so is everything while the text is empty or an invalid glob.
+    fn matches(&self, file: &Path) -> bool {
+        if self.text.is_empty() || file == Path::new("..") {
+            return true;
+        }
+        let Some(name) = file.file_name() else {
+            return false;
+        };
+        let name = name.to_string_lossy();
+        match (self.mode, &self.glob) {
+            (FilterMode::Contains, _) => {
+                name.to_lowercase().contains(&self.text.to_lowercase())
+            }
+            (FilterMode::Glob, Some(glob)) => glob.matches(&name),
+            (FilterMode::Glob, None) => true,
+        }
+    }
+}
+
+/// Colour scheme for the whole window; kept in the settings file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Theme {
+    #[default]
+    Light,
+    Dark,
+}
+
+impl Theme {
+    fn toggled(self) -> Self {
+        match self {
+            Theme::Light => Theme::Dark,
+            Theme::Dark => Theme::Light,
+        }
+    }
+
+    fn background(self) -> Color {
+        match self {
+            Theme::Light => Color::WHITE,
+            Theme::Dark => Color::from_rgb(0.13, 0.13, 0.15),
+        }
+    }
+
+    fn text(self) -> Color {
+        match self {
+            Theme::Light => Color::BLACK,
+            Theme::Dark => Color::from_rgb(0.9, 0.9, 0.9),
+        }
+    }
+
+    /// For secondary details such as link targets and stale sizes.
+    fn dimmed(self) -> Color {
+        match self {
+            Theme::Light => Color::from_rgb(0.5, 0.5, 0.5),
+            Theme::Dark => Color::from_rgb(0.6, 0.6, 0.6),
+        }
+    }
+
+    /// For lines only in the first of two compared files.
+    fn removed(self) -> Color {
+        match self {
+            Theme::Light => Color::from_rgb(0.7, 0.1, 0.1),
+            Theme::Dark => Color::from_rgb(0.95, 0.45, 0.45),
+        }
+    }
+
+    /// For lines only in the second of two compared files.
+    fn added(self) -> Color {
+        match self {
+            Theme::Light => Color::from_rgb(0.1, 0.5, 0.1),
+            Theme::Dark => Color::from_rgb(0.45, 0.85, 0.45),
+        }
+    }
+}
+
+/// A colour label for an entry, like Finder's tags; kept in the settings file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagColor {
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Blue,
+    Purple,
+    Gray,
+}
+
+impl TagColor {
+    const ALL: [TagColor; 7] = [
+        TagColor::Red,
+        TagColor::Orange,
+        TagColor::Yellow,
+        TagColor::Green,
+        TagColor::Blue,
+        TagColor::Purple,
+        TagColor::Gray,
+    ];
+
+    /// The name the settings file uses, also shown on the tag buttons.
+    fn name(self) -> &'static str {
+        match self {
+            TagColor::Red => "red",
+            TagColor::Orange => "orange",
+            TagColor::Yellow => "yellow",
+            TagColor::Green => "green",
+            TagColor::Blue => "blue",
+            TagColor::Purple => "purple",
+            TagColor::Gray => "gray",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<TagColor> {
+        TagColor::ALL.into_iter().find(|tag| tag.name() == name)
+    }
+
+    /// The dot drawn before a tagged entry's name; mid tones that read on either theme.
+    fn color(self) -> Color {
+        match self {
+            TagColor::Red => Color::from_rgb(0.9, 0.25, 0.25),
+            TagColor::Orange => Color::from_rgb(0.95, 0.55, 0.15),
+            TagColor::Yellow => Color::from_rgb(0.9, 0.75, 0.1),
+            TagColor::Green => Color::from_rgb(0.25, 0.7, 0.3),
+            TagColor::Blue => Color::from_rgb(0.25, 0.5, 0.95),
+            TagColor::Purple => Color::from_rgb(0.6, 0.35, 0.85),
+            TagColor::Gray => Color::from_rgb(0.55, 0.55, 0.55),
+        }
+    }
+}
+
+/// What it takes to open a file from the list; kept in the settings file. Folders open
+/// on a single click either way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum ClickMode {
+    #[default]
+    DoubleClick,
+    SingleClick,
+}
+
+impl ClickMode {
+    const DEFAULT_THRESHOLD_MS: u64 = 500;
+}
+
+/// Remembers the last click on an entry to tell double-clicks from single ones.
+#[derive(Debug, Clone, Default)]
+struct ClickTracker {
+    last: Option<(PathBuf, Instant)>,  // Last clicked entry and when
+}
+
+impl ClickTracker {
+    /// Records a click on `path` at `now`, returning whether it completes a double-click:
+    /// a second click on the same entry less than `threshold` after the first. A third
+    /// click then starts over rather than counting as another double-click.
+    fn click(&mut self, path: &Path, now: Instant, threshold: Duration) -> bool {
+        let double = matches!(
+            &self.last,
+            Some((last_path, last_time))
+                if *last_path == path && now.duration_since(*last_time) < threshold
+        );
+        self.last = if double { None } else { Some((path.to_path_buf(), now)) };
+        double
+    }
+
+    /// Forgets the last click, so the next one can't complete a double-click.
+    fn reset(&mut self) {
+        self.last = None;
+    }
+}
+
+/// How row icons are drawn; kept in the settings file. Emoji look different from one
+/// platform's fonts to the next, so plain text labels are offered as well.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum IconSet {
+    #[default]
+    Emoji,
+    Text,
+}
+
+/// How symlink targets are shown; kept in the settings file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum LinkTargetStyle {
+    #[default]
+    Raw,  // As stored in the link, which may be relative to it
+    Absolute,  // Fully resolved: every link along the way followed
+}
+
+// Limits for the recursive walk behind the flatten view
+const FLATTEN_MAX_DEPTH: usize = 16;
+const FLATTEN_MAX_FILES: usize = 10_000;
+
+// Height of one file row plus the column spacing, used to scroll a row into view
+const ROW_STRIDE: f32 = 35.0;
+
+// Files dropped this close together came from the same drag; iced reports each one
+// of a multi-file drop separately
+const DROP_BATCH_WINDOW: Duration = Duration::from_millis(500);
+
+// A pause this long between keystrokes starts a new type-ahead search
+const TYPE_AHEAD_TIMEOUT: Duration = Duration::from_secs(1);
+
+// Rows built around the scroll position; the rest of the list is blank space of the
+// same height. Enough to fill a tall window with some to spare on either side.
+const RENDER_WINDOW: usize = 80;
+const RENDER_OVERSCAN: usize = 20;
+
+// Folders whose sort and scroll position we remember; the least recently visited
+// is forgotten first
+const VIEW_STATE_LIMIT: usize = 256;
+
+// Entries deleted per background step; progress is shown between steps, and a cancel
+// takes effect within one
+const DELETE_BATCH: usize = 200;
+
+// Files and folders pasted per background step; a cancel waits for the file being
+// copied, however big
+const PASTE_BATCH: usize = 200;
+
+// Folders pasted into that are offered as quick paste targets, most recent first
+const RECENT_DESTINATIONS: usize = 5;
+
+// Breadcrumbs shown before the middle of a deep path is elided
+const BREADCRUMB_MAX: usize = 6;
+
+// Widths of the size and modified-date columns in the file list
+const SIZE_COLUMN_WIDTH: u16 = 90;
+const MODIFIED_COLUMN_WIDTH: u16 = 140;
+
+/// How a folder was last being viewed, restored when we come back to it.
+#[derive(Debug, Clone, Copy)]
+struct ViewState {
+    sort_key: SortKey,
+    sort_ascending: bool,
+    scroll_offset: f32,
+    last_used: u64,  // `view_state_clock` at the last visit, for evicting old entries
+}
+
+impl Default for ViewState {
+    fn default() -> Self {
+        ViewState {
+            sort_key: SortKey::default(),
+            sort_ascending: true,
+            scroll_offset: 0.0,
+            last_used: 0,
+        }
+    }
+}
+
+/// Somewhere the user asked to go while a dialog still held unapplied input, kept
+/// until they confirm discarding it.
+#[derive(Debug, Clone)]
+enum Leave {
+    Navigate(PathBuf),
+    History(usize),  // Index into the current tab's history
+    NewTab(PathBuf),
+    SelectTab(usize),
+    CloseTab(usize),
+    Quit,
 }
 
+/// One open folder: its listing, selection, history and scroll position. The tab
+/// strip switches between them; everything else in `FileExplorer` is shared.
 #[derive(Default)]
-struct FileExplorer {
+struct Tab {
     path: PathBuf,
     files: Vec<PathBuf>,
+    entry_meta: HashMap<PathBuf, EntryMeta>,  // Read when listing, for every entry but `..`
+    summary: ListingSummary,  // Covers the entries that pass the filter
     scroll: scrollable::State,
+    history: Vec<PathBuf>,  // Folders visited, oldest first
+    history_index: usize,  // Position of the current folder in `history`
+    file_buttons: Vec<button::State>,
+    delete_buttons: Vec<button::State>,
+    rename_buttons: Vec<button::State>,
+    copy_path_buttons: Vec<button::State>,
+    properties_buttons: Vec<button::State>,
+    selected_index: Option<usize>,  // Row the keyboard cursor is on, in `files`
+    selected: HashSet<PathBuf>,  // Entries batch actions apply to; never `..`
+    selection_anchor: Option<usize>,  // Where a Shift-click range starts
+    select_on_load: Option<PathBuf>,  // Entry to highlight once the pending listing arrives
+    loading: bool,  // A listing of `path` is being read in the background
+    flatten: bool,  // Show every descendant file instead of the directory
+    access_denied: bool,  // The last listing of `path` was refused for lack of permission
+}
+
+/// A delete running in the background. The entries to remove are gathered first, then
+/// deleted `DELETE_BATCH` at a time.
+struct DeleteJob {
+    items: Option<Vec<PathBuf>>,  // None while they're still being gathered
+    done: usize,  // Entries of `items` dealt with so far
+    deleted: usize,
+    kept: usize,  // Folders left because something inside couldn't be deleted
+    failures: Vec<String>,
+    blocked: HashSet<PathBuf>,  // In transit while a step runs; see `delete_batch`
+    cancel: Arc<AtomicBool>,
+}
+
+/// A copy or move running in the background. What to do is planned first, then done
+/// `PASTE_BATCH` files and folders at a time.
+struct PasteJob {
+    sources: Vec<PathBuf>,
+    dest: PathBuf,
+    op: ClipOp,
+    action: &'static str,  // "Paste" or "Copy", for the messages
+    remember_dest: bool,  // Offer `dest` as a quick paste target afterwards
+    steps: Option<Vec<PasteStep>>,  // None while they're still being planned
+    done: usize,  // Steps dealt with so far
+    progress: PasteProgress,  // Taken by the step that's running
+    cancel: Arc<AtomicBool>,
+}
+
+impl Tab {
+    /// The entry the keyboard cursor rests on, if any.
+    fn cursor_entry(&self) -> Option<&PathBuf> {
+        self.selected_index.and_then(|index| self.files.get(index))
+    }
+}
+
+#[derive(Default)]
+struct FileExplorer {
+    tabs: Vec<Tab>,  // Never empty
+    active_tab: usize,
+    tab_buttons: Vec<button::State>,
+    close_tab_buttons: Vec<button::State>,
+    new_tab_button: button::State,
     drives_scroll: scrollable::State,
     refresh_button: button::State,
     up_button: button::State,
+    home_button: button::State,
+    back_button: button::State,
+    forward_button: button::State,
     drive_button: button::State,
-    file_buttons: Vec<button::State>,
+    flatten_button: button::State,
+    paste_button: button::State,
+    copy_button: button::State,
+    cut_button: button::State,
+    paste_here_button: button::State,
+    paste_to_buttons: Vec<button::State>,  // One per shown recent destination
+    change_ext_button: button::State,
+    change_ext_targets: Vec<PathBuf>,  // Files the extension prompt applies to; empty when closed
+    change_ext_input: text_input::State,
+    change_ext_value: String,
+    change_ext_renames: Vec<(PathBuf, PathBuf)>,  // What applying would do, skipping no-ops
+    change_ext_error: Option<String>,
+    apply_change_ext_button: button::State,
+    compare_button: button::State,
+    diff_view: Option<DiffView>,
+    clipboard_op: Option<(Vec<PathBuf>, ClipOp)>,  // Entries copied or cut inside the app
+    preview: Option<Preview>,  // Set while a single image is selected
+    parent_style_button: button::State,
+    folder_counts_button: button::State,
+    hidden_button: button::State,
+    dir_sizes_button: button::State,
+    siblings_button: button::State,
+    siblings_scroll: scrollable::State,
+    sibling_buttons: Vec<button::State>,
+    breadcrumb_buttons: Vec<button::State>,
+    siblings: Vec<PathBuf>,  // Subfolders of the parent, shown by the sibling switcher
+    show_siblings: bool,
+    properties: Option<(PathBuf, Vec<(&'static str, String)>)>,  // Panel for this entry
+    close_properties_button: button::State,
+    properties_is_dir: bool,  // Whether the panel's entry is a folder, for custom actions
+    custom_action_buttons: Vec<button::State>,  // One per entry of `config.custom_actions`
+    tag_buttons: [button::State; TagColor::ALL.len() + 1],  // One per colour, then "None"
+    reveal_button: button::State,
+    renaming: Option<PathBuf>,  // Entry whose row is currently an edit field
+    rename_input: text_input::State,
+    rename_value: String,
+    rename_error: Option<String>,
+    pending_delete: Vec<PathBuf>,  // Entries awaiting delete confirmation
+    confirm_delete_button: button::State,
+    cancel_delete_button: button::State,
+    delete_job: Option<DeleteJob>,
+    cancel_delete_job_button: button::State,
+    paste_job: Option<PasteJob>,
+    cancel_paste_job_button: button::State,
+    pending_drop: Vec<PathBuf>,  // Dropped entries offered for copying into the folder
+    last_drop: Option<Instant>,  // When the last file was dropped on the window
+    type_ahead: String,  // Lowercased name prefix typed so far to jump to an entry
+    type_ahead_at: Option<Instant>,  // When a character was last added to it
+    confirm_drop_button: button::State,
+    cancel_drop_button: button::State,
     drives: Vec<PathBuf>,
     drive_buttons: Vec<button::State>,
     show_drives: bool,
-    last_click_time: Option<Instant>,  // Track the last click time
+    modifiers: keyboard::Modifiers,  // Held keys, so clicks can tell Ctrl/Shift apart
+    show_hidden: bool,  // List dotfiles / entries with the Windows hidden attribute
+    filter_input: text_input::State,
+    filter: NameFilter,
+    filter_mode_button: button::State,
+    sort_key: SortKey,
+    sort_ascending: bool,
+    view_states: HashMap<PathBuf, ViewState>,  // Per folder, capped at VIEW_STATE_LIMIT
+    view_state_clock: u64,  // Bumped on every save or restore of a view state
+    sort_name_button: button::State,
+    sort_size_button: button::State,
+    sort_modified_button: button::State,
+    show_folder_counts: bool,
+    folder_count_cache: HashMap<PathBuf, Option<usize>>,  // None when the folder can't be read
+    show_dir_sizes: bool,
+    dir_size_cache: HashMap<PathBuf, DirSize>,
+    dir_size_cancel: Arc<AtomicBool>,  // Set to abandon size walks for the folder we left
+    dir_size_pending: HashSet<PathBuf>,  // Folders a size walk is running for
+    copy_breakdown_button: button::State,
+    listing_cancel: Arc<AtomicBool>,  // Set to abandon a listing that's been superseded
+    parent_entry_style: ParentEntryStyle,
+    goto_open: bool,  // "Go to" dialog (Ctrl+G) is showing
+    goto_input: text_input::State,
+    goto_value: String,
+    goto_error: Option<String>,
+    new_folder_button: button::State,
+    new_folder_open: bool,  // Name prompt for a new folder is showing
+    new_folder_input: text_input::State,
+    new_folder_name: String,
+    new_folder_error: Option<String>,
+    address_input: text_input::State,
+    address_value: String,  // Mirrors `path` except while the user is editing it
+    address_error: Option<String>,
+    error_message: Option<String>,  // Shown in the banner until dismissed or we navigate
+    notice: Option<String>,  // Confirmation in the status bar, cleared when we navigate
+    dismiss_error_button: button::State,
+    #[cfg(unix)]
+    ipc_listener: Option<std::sync::Arc<std::os::unix::net::UnixListener>>,
+    clicks: ClickTracker,
+    launcher: Box<dyn Launcher>,  // Opens and reveals entries outside the app
+    config: config::Config,  // What we remember between runs
+    bookmark_button: button::State,
+    theme_button: button::State,
+    click_mode_button: button::State,
+    icon_set_button: button::State,
+    link_style_button: button::State,
+    bookmark_buttons: Vec<button::State>,
+    remove_bookmark_buttons: Vec<button::State>,
+    bookmark_exists: Vec<bool>,  // Per entry of `config.bookmarks`, checked on navigation
+    pending_leave: Option<Leave>,  // Waiting on "Discard" while a dialog holds input
+    discard_button: button::State,
+    keep_editing_button: button::State,
+    exiting: bool,  // The window may close
+}
+
+impl FileExplorer {
+    /// The tab being shown, which every listing and navigation acts on.
+    fn tab(&self) -> &Tab {
+        &self.tabs[self.active_tab]
+    }
+
+    fn tab_mut(&mut self) -> &mut Tab {
+        &mut self.tabs[self.active_tab]
+    }
+
+    /// Opens `dir` in a new tab after the others and brings it to the front.
+    fn open_in_new_tab(&mut self, dir: PathBuf) -> Command<Message> {
+        if self.leave_blocked(Leave::NewTab(dir.clone())) {
+            return Command::none();
+        }
+        self.save_view_state();
+        self.tabs.push(Tab::default());
+        self.active_tab = self.tabs.len() - 1;
+        self.navigate_to(dir)
+    }
+
+    /// Opens the entry under the cursor: folders in the app, files with the app the
+    /// system associates with them.
+    fn open_selected(&mut self) -> Command<Message> {
+        let Some(entry) = self.tab().cursor_entry() else {
+            return Command::none();
+        };
+        let target = self.resolve_entry(entry);
+        if self.entry_vanished(entry, &target) {
+            return self.report_vanished(&target);
+        }
+        if target.is_dir() || !target.exists() {
+            return self.navigate_to(target);
+        }
+        if let Err(err) = self.launcher.open(&target) {
+            self.error_message =
+                Some(format!("Failed to open {}: {}", display_path(&target), err));
+        }
+        Command::none()
+    }
+
+    /// Goes to what the address bar says, or explains under the field why it can't.
+    fn submit_address(&mut self) -> Command<Message> {
+        let target = fs_ops::expand_path(&self.address_value, &self.tab().path)
+            .and_then(|path| fs::canonicalize(&path).map_err(|err| err.to_string()));
+        match target {
+            Ok(target) => self.navigate_to(target),
+            Err(err) => {
+                // Leave the listing alone; the error shows under the field
+                self.address_error = Some(err);
+                Command::none()
+            }
+        }
+    }
+
+    /// Central entry point for moving to a location. A folder is opened directly; a file
+    /// opens its parent folder with the file selected and scrolled into view.
+    fn navigate_to(&mut self, target: PathBuf) -> Command<Message> {
+        if self.leave_blocked(Leave::Navigate(target.clone())) {
+            return Command::none();
+        }
+        let target = long_path(&target);
+        let (dir, file) = if target.is_dir() {
+            (target, None)
+        } else if let Some(parent) = target.parent().filter(|_| target.exists()) {
+            (parent.to_path_buf(), Some(target.clone()))
+        } else if let Ok(link) = fs::read_link(&target) {
+            // The link itself is there; what it points at isn't
+            self.error_message = Some(format!(
+                "Cannot open {}: it links to {}, which does not exist",
+                display_path(&target),
+                display_path(&link)
+            ));
+            return Command::none();
+        } else {
+            self.error_message = Some(format!(
+                "Cannot open {}: it no longer exists",
+                display_path(&target)
+            ));
+            return Command::none();
+        };
+
+        // Taking a new branch drops whatever was ahead of us
+        let tab = self.tab_mut();
+        if tab.history.get(tab.history_index) != Some(&dir) {
+            tab.history.truncate(tab.history_index + 1);
+            tab.history.push(dir.clone());
+            tab.history_index = tab.history.len() - 1;
+        }
+
+        let command = self.enter_directory(dir);
+        self.tab_mut().select_on_load = file;
+        command
+    }
+
+    /// What a dialog holds that leaving would throw away, when the `confirm_discard`
+    /// setting asks to be warned about it.
+    fn unsaved_input(&self) -> Option<String> {
+        if !self.config.confirm_discard {
+            return None;
+        }
+        if let Some(target) = &self.renaming {
+            let original = target.file_name().map(|name| name.to_string_lossy());
+            if original.as_deref() != Some(self.rename_value.as_str()) {
+                return Some("rename".to_string());
+            }
+        }
+        if !self.change_ext_targets.is_empty() {
+            return Some(format!("extension change of {} files", self.change_ext_targets.len()));
+        }
+        if self.new_folder_open && !self.new_folder_name.trim().is_empty() {
+            return Some("new folder".to_string());
+        }
+        if !self.pending_delete.is_empty() {
+            return Some("delete".to_string());
+        }
+        if !self.pending_drop.is_empty() {
+            return Some("copy of the dropped files".to_string());
+        }
+        None
+    }
+
+    /// Holds `leave` back for confirmation if doing it now would throw away input a
+    /// dialog holds. Returns whether it was held back.
+    fn leave_blocked(&mut self, leave: Leave) -> bool {
+        if self.unsaved_input().is_none() {
+            return false;
+        }
+        self.pending_leave = Some(leave);
+        true
+    }
+
+    /// Closes every dialog, dropping what was typed into them.
+    fn close_dialogs(&mut self) {
+        self.properties = None;
+        self.change_ext_targets.clear();
+        self.diff_view = None;
+        self.goto_open = false;
+        self.new_folder_open = false;
+        self.pending_delete.clear();
+        self.pending_drop.clear();
+        self.renaming = None;
+        self.pending_leave = None;
+    }
+
+    /// Drops what the dialogs hold and goes where the user asked to before being
+    /// warned about it.
+    fn discard_and_leave(&mut self) -> Command<Message> {
+        let Some(leave) = self.pending_leave.take() else {
+            return Command::none();
+        };
+        self.close_dialogs();
+        match leave {
+            Leave::Navigate(target) => self.navigate_to(target),
+            Leave::History(index) => self.go_to_history(index),
+            Leave::NewTab(dir) => self.open_in_new_tab(dir),
+            Leave::SelectTab(index) => self.select_tab(index),
+            Leave::CloseTab(index) => self.close_tab(index),
+            Leave::Quit => {
+                self.exiting = true;
+                Command::none()
+            }
+        }
+    }
+
+    /// Goes to entry `index` of the current tab's history, as Back and Forward do.
+    fn go_to_history(&mut self, index: usize) -> Command<Message> {
+        if index >= self.tab().history.len() || self.leave_blocked(Leave::History(index)) {
+            return Command::none();
+        }
+        let tab = self.tab_mut();
+        tab.history_index = index;
+        let dir = tab.history[index].clone();
+        self.enter_directory(dir)
+    }
+
+    /// Brings tab `index` to the front, remembering how the one being left was viewed.
+    fn select_tab(&mut self, index: usize) -> Command<Message> {
+        if index == self.active_tab || index >= self.tabs.len() {
+            return Command::none();
+        }
+        if self.leave_blocked(Leave::SelectTab(index)) {
+            return Command::none();
+        }
+        self.save_view_state();
+        self.switch_to_tab(index)
+    }
+
+    /// Closes tab `index`, bringing a neighbour to the front if it was the current one.
+    fn close_tab(&mut self, index: usize) -> Command<Message> {
+        if index >= self.tabs.len() {
+            return Command::none();
+        }
+        if index == self.active_tab {
+            if self.leave_blocked(Leave::CloseTab(index)) {
+                return Command::none();
+            }
+            self.save_view_state();
+        }
+        let closed = self.tabs.remove(index);
+        if self.tabs.is_empty() {
+            // Closing the last tab leaves a fresh one rather than an empty window
+            self.tabs.push(Tab::default());
+            self.active_tab = 0;
+            return self.navigate_to(fs_ops::home_dir().unwrap_or(closed.path));
+        }
+        if index < self.active_tab {
+            self.active_tab -= 1;
+            Command::none()
+        } else if index == self.active_tab {
+            self.switch_to_tab(index.min(self.tabs.len() - 1))
+        } else {
+            Command::none()
+        }
+    }
+
+    /// Makes `dir` the current folder and lists it, resetting per-folder view state.
+    /// History is left alone, so Back/Forward can use this as well.
+    fn enter_directory(&mut self, dir: PathBuf) -> Command<Message> {
+        self.save_view_state();
+        // The old folder's subfolders won't be shown, so stop measuring them
+        self.cancel_dir_sizes();
+        // A successful navigation clears any stale error; listing may set a fresh one
+        self.error_message = None;
+        self.notice = None;
+        self.filter.set(String::new(), self.filter.mode);
+        self.address_value = display_path(&dir);
+        self.tab_mut().path = dir;
+        self.address_error = None;
+        self.show_drives = false;
+        self.show_siblings = false;
+        self.pending_delete.clear();
+        self.pending_drop.clear();
+        self.change_ext_targets.clear();
+        self.diff_view = None;
+        self.renaming = None;
+        self.properties = None;
+        self.preview = None;
+        let tab = self.tab_mut();
+        tab.selected.clear();
+        tab.flatten = false;
+        // Don't leave the old folder's entries up while the new one loads
+        tab.files.clear();
+        tab.entry_meta.clear();
+        self.remember_directory();
+        self.refresh_bookmarks();
+        let scroll_offset = self.restore_view_state();
+        self.scroll_to_offset(scroll_offset);
+        self.list_files()
+    }
+
+    /// Records the current tab's sort and scroll position under its folder, forgetting
+    /// the least recently used folder once `VIEW_STATE_LIMIT` are remembered. Call it
+    /// before leaving the folder or the tab.
+    fn save_view_state(&mut self) {
+        let tab = &self.tabs[self.active_tab];
+        // A tab that was just opened hasn't shown a folder yet
+        if tab.path.as_os_str().is_empty() {
+            return;
+        }
+        let viewport = iced::Rectangle { x: 0.0, y: 0.0, width: 0.0, height: 0.0 };
+        let content = iced::Rectangle { height: f32::MAX, ..viewport };
+        self.view_state_clock += 1;
+        let state = ViewState {
+            sort_key: self.sort_key,
+            sort_ascending: self.sort_ascending,
+            scroll_offset: tab.scroll.offset(viewport, content) as f32,
+            last_used: self.view_state_clock,
+        };
+        let dir = tab.path.clone();
+        if !self.view_states.contains_key(&dir) && self.view_states.len() >= VIEW_STATE_LIMIT {
+            let oldest = self
+                .view_states
+                .iter()
+                .min_by_key(|(_, state)| state.last_used)
+                .map(|(dir, _)| dir.clone());
+            if let Some(oldest) = oldest {
+                self.view_states.remove(&oldest);
+            }
+        }
+        self.view_states.insert(dir, state);
+    }
+
+    /// Puts back the sort remembered for the current tab's folder, or the defaults for
+    /// a folder we haven't seen, and returns the scroll offset it was left at.
+    fn restore_view_state(&mut self) -> f32 {
+        self.view_state_clock += 1;
+        let clock = self.view_state_clock;
+        let state = match self.view_states.get_mut(&self.tabs[self.active_tab].path) {
+            Some(state) => {
+                state.last_used = clock;
+                *state
+            }
+            None => ViewState::default(),
+        };
+        self.sort_key = state.sort_key;
+        self.sort_ascending = state.sort_ascending;
+        state.scroll_offset
+    }
+
+    /// Brings tab `index` to the front. Dialogs and the filter belonged to the tab being
+    /// left, so they're dropped; the tab's folder is re-listed to catch changes made
+    /// while it was in the background.
+    fn switch_to_tab(&mut self, index: usize) -> Command<Message> {
+        self.cancel_dir_sizes();
+        self.active_tab = index;
+        self.error_message = None;
+        self.notice = None;
+        self.filter.set(String::new(), self.filter.mode);
+        self.address_value = display_path(&self.tab().path);
+        self.address_error = None;
+        self.show_siblings = false;
+        self.pending_delete.clear();
+        self.pending_drop.clear();
+        self.change_ext_targets.clear();
+        self.diff_view = None;
+        self.renaming = None;
+        self.properties = None;
+        self.preview = None;
+        self.clicks.reset();
+        let tab = self.tab_mut();
+        tab.select_on_load = tab.cursor_entry().cloned();
+        self.remember_directory();
+        self.refresh_bookmarks();
+        // The tab kept its own scroll position; only the sort is shared between tabs
+        self.restore_view_state();
+        self.list_files()
+    }
+
+    /// Starts copying or moving `sources` into `dest` in the background. A name that's
+    /// taken gets a " (copy)" suffix. `action` names what's being done ("Paste",
+    /// "Copy") in the messages; `remember_dest` puts `dest` among the recent
+    /// destinations once anything arrives there.
+    fn start_paste(
+        &mut self,
+        sources: Vec<PathBuf>,
+        dest: PathBuf,
+        op: ClipOp,
+        action: &'static str,
+        remember_dest: bool,
+    ) -> Command<Message> {
+        if let Some(job) = &self.paste_job {
+            self.error_message = Some(format!(
+                "Wait for the current {} to finish or cancel it",
+                job.action.to_lowercase()
+            ));
+            return Command::none();
+        }
+        let cancel = Arc::new(AtomicBool::new(false));
+        let planned = (sources.clone(), dest.clone(), cancel.clone());
+        self.paste_job = Some(PasteJob {
+            sources,
+            dest,
+            op,
+            action,
+            remember_dest,
+            steps: None,
+            done: 0,
+            progress: PasteProgress::default(),
+            cancel,
+        });
+        Command::perform(
+            async move {
+                let (sources, dest, cancel) = planned;
+                fs_ops::plan_paste(&sources, &dest, op, &cancel)
+            },
+            Message::PastePlanned,
+        )
+    }
+
+    /// Starts the next step of the running paste, or wraps it up once everything has
+    /// been dealt with or it was cancelled: the folder is re-listed and the outcome
+    /// reported.
+    fn next_paste_batch(&mut self) -> Command<Message> {
+        let Some(job) = &mut self.paste_job else {
+            return Command::none();
+        };
+        let steps = job.steps.as_deref().unwrap_or_default();
+        let cancelled = job.cancel.load(Ordering::Relaxed);
+        if !cancelled && job.done < steps.len() {
+            let batch = steps[job.done..(job.done + PASTE_BATCH).min(steps.len())].to_vec();
+            let mut progress = std::mem::take(&mut job.progress);
+            let cancel = job.cancel.clone();
+            return Command::perform(
+                async move {
+                    let done = fs_ops::paste_batch(&batch, &mut progress, &cancel);
+                    (done, progress)
+                },
+                |(done, progress)| Message::PasteProgress(done, progress),
+            );
+        }
+
+        let Some(job) = self.paste_job.take() else {
+            return Command::none();
+        };
+        let verb = if job.action == "Copy" { "Copied" } else { "Pasted" };
+        let mut notice = format!(
+            "{} {} items into {}",
+            verb,
+            job.progress.pasted,
+            display_path(&job.dest)
+        );
+        if cancelled {
+            notice.push_str(&format!(" before the {} was cancelled", job.action.to_lowercase()));
+        }
+        self.notice = Some(notice);
+        let mut failed: Vec<_> = job.progress.failed.iter().collect();
+        if !failed.is_empty() {
+            failed.sort_by_key(|(entry, _)| **entry);
+            const SHOWN: usize = 5;
+            let shown: Vec<_> = failed
+                .iter()
+                .take(SHOWN)
+                .map(|(entry, err)| format!("{}: {}", display_path(&job.sources[**entry]), err))
+                .collect();
+            let mut message =
+                format!("{} failed for {} items: {}", job.action, failed.len(), shown.join("; "));
+            if failed.len() > SHOWN {
+                message.push_str(&format!("; and {} more", failed.len() - SHOWN));
+            }
+            self.error_message = Some(message);
+        }
+        if job.remember_dest && job.progress.pasted > 0 {
+            self.remember_destination(job.dest);
+        }
+        self.list_files()
+    }
+
+    /// Moves `dest` to the front of the recent paste destinations, dropping the oldest
+    /// past `RECENT_DESTINATIONS`.
+    fn remember_destination(&mut self, dest: PathBuf) {
+        let dest = PathBuf::from(display_path(&dest));
+        let recent = &mut self.config.recent_destinations;
+        recent.retain(|dir| *dir != dest);
+        recent.insert(0, dest);
+        recent.truncate(RECENT_DESTINATIONS);
+        // Like the last folder, not worth interrupting the user over
+        let _ = self.config.save();
+    }
+
+    /// Saves the current folder as the one to reopen next launch.
+    fn remember_directory(&mut self) {
+        let dir = PathBuf::from(display_path(&self.tab().path));
+        if self.config.last_dir.as_ref() != Some(&dir) {
+            self.config.last_dir = Some(dir);
+            // Not worth interrupting the user over; we just start elsewhere next time
+            let _ = self.config.save();
+        }
+    }
+
+    /// Re-checks which bookmarks still exist, so the sidebar can mark the missing ones
+    /// without touching the filesystem on every redraw.
+    fn refresh_bookmarks(&mut self) {
+        self.bookmark_exists = self.config.bookmarks.iter().map(|dir| dir.is_dir()).collect();
+    }
+
+    /// Persists the bookmark list after a change, reporting a failed save in the banner.
+    fn save_bookmarks(&mut self) {
+        self.refresh_bookmarks();
+        if let Err(err) = self.config.save() {
+            self.error_message = Some(format!("Cannot save bookmarks: {}", err));
+        }
+    }
+
+    /// Moves the selection one visible row down (`forward`) or up. With nothing
+    /// selected yet, the first visible row is selected. Holding Shift extends the
+    /// selection from the anchor instead; holding Ctrl moves only the cursor, leaving
+    /// the selection for Ctrl+Space to change.
+    fn move_selection(&mut self, forward: bool) {
+        // Rows hidden by the filter are skipped
+        let tab = self.tab();
+        let visible: Vec<usize> = (0..tab.files.len())
+            .filter(|index| self.filter.matches(&tab.files[*index]))
+            .collect();
+        if visible.is_empty() {
+            return;
+        }
+        let current = tab
+            .selected_index
+            .and_then(|selected| visible.iter().position(|index| *index == selected));
+        let row = match current {
+            None => 0,
+            Some(row) if forward => (row + 1).min(visible.len() - 1),
+            Some(row) => row.saturating_sub(1),
+        };
+        let index = visible[row];
+        if self.modifiers.shift {
+            if self.tab().selection_anchor.is_none() {
+                self.tab_mut().selection_anchor = Some(current.map_or(index, |row| visible[row]));
+            }
+            self.extend_selection(index, true);
+        } else if self.modifiers.is_command_pressed() {
+            self.tab_mut().selected_index = Some(index);
+        } else {
+            self.select_only(index);
+        }
+        self.scroll_to_row(row);
+    }
+
+    /// Type-ahead: moves the cursor to the first shown entry whose name starts with what
+    /// has been typed (`c` included) since the last pause. Typing one letter over and over
+    /// steps through the entries starting with it instead. A keystroke that matches
+    /// nothing is dropped and the cursor stays put.
+    fn jump_to_prefix(&mut self, c: char) -> Command<Message> {
+        let now = Instant::now();
+        if self.type_ahead_at.is_none_or(|at| now.duration_since(at) >= TYPE_AHEAD_TIMEOUT) {
+            self.type_ahead.clear();
+        }
+        self.type_ahead_at = Some(now);
+
+        let mut typed = self.type_ahead.clone();
+        typed.extend(c.to_lowercase());
+        let mut letters = typed.chars();
+        let first = letters.next();
+        let cycling = typed.chars().count() > 1 && letters.all(|letter| Some(letter) == first);
+        let prefix = if cycling { c.to_lowercase().collect() } else { typed.clone() };
+
+        let tab = self.tab();
+        let visible: Vec<usize> = (0..tab.files.len())
+            .filter(|index| tab.files[*index] != Path::new(".."))
+            .filter(|index| self.filter.matches(&tab.files[*index]))
+            .collect();
+        let matches = |index: &usize| {
+            tab.files[*index]
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().to_lowercase().starts_with(&prefix))
+        };
+        // Cycling starts looking just past the cursor and wraps around to the top
+        let start = match tab.selected_index.filter(|_| cycling) {
+            Some(current) => visible.iter().position(|index| *index > current).unwrap_or(0),
+            None => 0,
+        };
+        let found = visible[start..]
+            .iter()
+            .chain(&visible[..start])
+            .find(|index| matches(index))
+            .copied();
+
+        let Some(index) = found else {
+            return Command::none();
+        };
+        self.type_ahead = typed;
+        self.select_only(index);
+        // Rows above it that the filter hides don't take up space in the list
+        let row = self
+            .tab()
+            .files
+            .iter()
+            .take(index)
+            .filter(|file| self.filter.matches(file))
+            .count();
+        self.scroll_to_row(row);
+        self.selection_changed()
+    }
+
+    /// The path an entry in `files` stands for; `..` becomes the current folder's parent.
+    fn resolve_entry(&self, entry: &Path) -> PathBuf {
+        fs_ops::resolve_entry(&self.tab().path, entry)
+    }
+
+    /// Scrolls the file list so the row at `index` sits near the top.
+    fn scroll_to_row(&mut self, index: usize) {
+        self.scroll_to_offset(index.saturating_sub(2) as f32 * ROW_STRIDE);
+    }
+
+    /// Scrolls the file list to `offset` pixels from the top.
+    fn scroll_to_offset(&mut self, offset: f32) {
+        // scroll_to places the offset at `percentage` of the hidden height, so an empty
+        // viewport over content `offset` tall yields exactly `offset`. The scrollable
+        // clamps it to the real content on the next layout.
+        let viewport = iced::Rectangle { x: 0.0, y: 0.0, width: 0.0, height: 0.0 };
+        let content = iced::Rectangle { height: offset, ..viewport };
+        self.tab_mut().scroll.scroll_to(1.0, viewport, content);
+    }
+
+    /// Starts reading the current folder in the background; the listing replaces `files`
+    /// when it arrives. Slow folders (network shares, huge directories) don't block the UI.
+    fn list_files(&mut self) -> Command<Message> {
+        // Holding Alt shows hidden entries for as long as it's held
+        let show_hidden = self.show_hidden || self.modifiers.alt;
+        let tab = self.tab_mut();
+        tab.selected_index = None;
+        tab.selection_anchor = None;
+        tab.loading = true;
+
+        if tab.flatten {
+            // The walk and its stats can take a while on big trees, so run them off the UI thread
+            let root = tab.path.clone();
+            tab.files.clear();
+            tab.entry_meta.clear();
+            self.refresh_summary();
+            return Command::perform(
+                async move {
+                    let files = fs_ops::walk_files(
+                        &root,
+                        show_hidden,
+                        FLATTEN_MAX_DEPTH,
+                        FLATTEN_MAX_FILES,
+                    );
+                    let meta = EntryMeta::read_all(&files);
+                    (root, files, meta)
+                },
+                |(root, files, meta)| Message::FlattenLoaded(root, files, meta),
+            );
+        }
+
+        // Only the newest listing is wanted, whichever folder the older ones were for
+        self.listing_cancel.store(true, Ordering::Relaxed);
+        let cancel = Arc::new(AtomicBool::new(false));
+        self.listing_cancel = cancel.clone();
+
+        let dir = self.tab().path.clone();
+        Command::perform(
+            async move {
+                let listing = fs_ops::list_files_in_directory(&dir, show_hidden, &cancel)
+                    .map_err(|err| {
+                        (err.kind(), format!("Cannot read {}: {}", display_path(&dir), err))
+                    });
+                (dir, listing)
+            },
+            |(dir, listing)| Message::FilesLoaded(dir, listing),
+        )
+    }
+
+    /// Installs a listing read by `list_files`, sorted by the current sort key.
+    fn apply_listing(
+        &mut self,
+        listing: Result<Vec<(PathBuf, EntryMeta)>, (io::ErrorKind, String)>,
+    ) {
+        let (sort_key, ascending) = (self.sort_key, self.sort_ascending);
+        let show_parent = self.parent_entry_style != ParentEntryStyle::Hidden;
+        let tab = self.tab_mut();
+        tab.loading = false;
+        tab.access_denied = false;
+        let mut files = Vec::new();
+        if tab.path.parent().is_some() && show_parent {
+            files.push(PathBuf::from(".."));
+        }
+        match listing {
+            Ok(listing) => {
+                let mut entries: Vec<PathBuf> =
+                    listing.iter().map(|(path, _)| path.clone()).collect();
+                tab.entry_meta = listing.into_iter().collect();
+                // Hidden entries are already gone; the name filter is left to the view
+                sort_entries(&mut entries, &tab.entry_meta, sort_key, ascending);
+                // `..` stays pinned at the top whatever the sort
+                files.extend(entries);
+            }
+            // The list itself says so, next to the `..` that leads back out
+            Err((io::ErrorKind::PermissionDenied, _)) => {
+                tab.entry_meta.clear();
+                tab.access_denied = true;
+            }
+            Err((_, err)) => {
+                tab.entry_meta.clear();
+                self.error_message = Some(err);
+            }
+        }
+        self.tab_mut().files = files;
+        self.refresh_summary();
+        self.restore_selection();
+    }
+
+    /// Patches the listing with what the watch saw change, rather than re-reading the
+    /// folder, so the cursor and scroll position stay where they were.
+    fn apply_changes(&mut self, changes: watch::Changes) {
+        let (sort_key, ascending) = (self.sort_key, self.sort_ascending);
+        let show_hidden = self.show_hidden || self.modifiers.alt;
+        let tab = self.tab_mut();
+        let cursor = tab.cursor_entry().cloned();
+        let anchor = tab.selection_anchor.and_then(|index| tab.files.get(index).cloned());
+        for path in &changes.removed {
+            tab.entry_meta.remove(path);
+            tab.selected.remove(path);
+        }
+        for (path, meta) in changes.added.into_iter().chain(changes.modified) {
+            if show_hidden || !meta.hidden {
+                tab.entry_meta.insert(path, meta);
+            } else {
+                // Modified to have the hidden attribute
+                tab.selected.remove(&path);
+                tab.entry_meta.remove(&path);
+            }
+        }
+
+        let pinned_parent = tab.files.first().is_some_and(|file| file == Path::new(".."));
+        let mut entries: Vec<PathBuf> = tab.entry_meta.keys().cloned().collect();
+        sort_entries(&mut entries, &tab.entry_meta, sort_key, ascending);
+        tab.files = if pinned_parent { vec![PathBuf::from("..")] } else { Vec::new() };
+        tab.files.extend(entries);
+
+        let position = |files: &[PathBuf], entry: Option<PathBuf>| {
+            entry.and_then(|entry| files.iter().position(|file| *file == entry))
+        };
+        // A cursor on an entry that went away stays on the same row
+        let last_row = tab.files.len().checked_sub(1);
+        tab.selected_index = position(&tab.files, cursor).or_else(|| {
+            tab.selected_index.zip(last_row).map(|(index, last)| index.min(last))
+        });
+        tab.selection_anchor = position(&tab.files, anchor);
+        self.refresh_summary();
+    }
+
+    /// After a listing arrives: drops selected entries that are gone, then moves the
+    /// cursor to the entry queued in `select_on_load` (if it was listed), selecting it
+    /// when nothing else still is.
+    fn restore_selection(&mut self) {
+        let tab = self.tab_mut();
+        let meta = &tab.entry_meta;
+        tab.selected.retain(|entry| meta.contains_key(entry));
+        let Some(target) = tab.select_on_load.take() else {
+            return;
+        };
+        // Match by name; `target` may be spelled differently (e.g. `./x`, `a/../x`)
+        tab.selected_index = tab.files.iter().position(|entry| {
+            *entry == target || (!tab.flatten && entry.file_name() == target.file_name())
+        });
+        if let Some(index) = tab.selected_index {
+            tab.selection_anchor = Some(index);
+            if tab.selected.is_empty() && tab.files[index] != Path::new("..") {
+                tab.selected.insert(tab.files[index].clone());
+            }
+            self.scroll_to_row(index);
+        }
+    }
+
+    /// Points the preview pane at the selection: shown when exactly one image is
+    /// selected, collapsed otherwise. The file is read in the background.
+    fn update_preview(&mut self) -> Command<Message> {
+        let selected = &self.tab().selected;
+        let target = match selected.iter().next() {
+            Some(path) if selected.len() == 1 && is_image(path) => path.clone(),
+            _ => {
+                self.preview = None;
+                return Command::none();
+            }
+        };
+        if self.preview.as_ref().is_some_and(|preview| preview.path == target) {
+            return Command::none();
+        }
+
+        self.preview = Some(Preview { path: target.clone(), loaded: false, dimensions: None });
+        Command::perform(
+            async move {
+                let dimensions = image_dimensions(&target);
+                (target, dimensions)
+            },
+            |(path, dimensions)| Message::PreviewLoaded(path, dimensions),
+        )
+    }
+
+    /// Makes the row at `index` the only selected one (none, for `..`) and the anchor
+    /// for Shift-clicks.
+    fn select_only(&mut self, index: usize) {
+        let tab = self.tab_mut();
+        tab.selected_index = Some(index);
+        tab.selection_anchor = Some(index);
+        tab.selected.clear();
+        if let Some(entry) = tab.files.get(index).filter(|entry| *entry != Path::new("..")) {
+            tab.selected.insert(entry.clone());
+        }
+    }
+
+    /// Ctrl-click: toggles the row at `index` in the selection. Shift-click (`range`):
+    /// selects every row shown between the anchor and `index` instead.
+    fn extend_selection(&mut self, index: usize, range: bool) {
+        let filter = &self.filter;
+        let tab = &mut self.tabs[self.active_tab];
+        tab.selected_index = Some(index);
+        match tab.selection_anchor.filter(|_| range) {
+            Some(anchor) => {
+                let (start, end) = (anchor.min(index), anchor.max(index));
+                tab.selected = tab.files[start..=end]
+                    .iter()
+                    .filter(|entry| *entry != Path::new(".."))
+                    .filter(|entry| filter.matches(entry))
+                    .cloned()
+                    .collect();
+            }
+            None => {
+                let entry = &tab.files[index];
+                if entry != Path::new("..") && !tab.selected.remove(entry) {
+                    tab.selected.insert(entry.clone());
+                }
+                tab.selection_anchor = Some(index);
+            }
+        }
+    }
+
+    /// Starts the next step of the running delete, or wraps it up once everything has
+    /// been dealt with or it was cancelled: the folder is re-listed and the outcome
+    /// reported.
+    fn next_delete_batch(&mut self) -> Command<Message> {
+        let Some(job) = &mut self.delete_job else {
+            return Command::none();
+        };
+        let items = job.items.as_deref().unwrap_or_default();
+        let cancelled = job.cancel.load(Ordering::Relaxed);
+        if !cancelled && job.done < items.len() {
+            let batch = items[job.done..(job.done + DELETE_BATCH).min(items.len())].to_vec();
+            let mut blocked = std::mem::take(&mut job.blocked);
+            let cancel = job.cancel.clone();
+            return Command::perform(
+                async move {
+                    let outcomes = fs_ops::delete_batch(&batch, &mut blocked, &cancel);
+                    (outcomes, blocked)
+                },
+                |(outcomes, blocked)| Message::DeleteProgress(outcomes, blocked),
+            );
+        }
+
+        let Some(job) = self.delete_job.take() else {
+            return Command::none();
+        };
+        let mut notice = format!("Deleted {} items", job.deleted);
+        if job.kept > 0 {
+            notice.push_str(&format!(", kept {} folders that weren't emptied", job.kept));
+        }
+        if cancelled {
+            notice.push_str(" before the delete was cancelled");
+        }
+        self.notice = Some(notice);
+        if !job.failures.is_empty() {
+            // A big tree can fail thousands of times over; the first few say enough
+            const SHOWN: usize = 5;
+            let mut message = format!(
+                "Failed to delete {} items: {}",
+                job.failures.len(),
+                job.failures[..job.failures.len().min(SHOWN)].join("; ")
+            );
+            if job.failures.len() > SHOWN {
+                message.push_str(&format!("; and {} more", job.failures.len() - SHOWN));
+            }
+            self.error_message = Some(message);
+        }
+        let tab = self.tab_mut();
+        tab.select_on_load = tab.cursor_entry().cloned();
+        self.list_files()
+    }
+
+    /// The selected entries in list order, or just `entry` when it isn't part of a
+    /// multi-selection. Row buttons use this so they act on the whole selection.
+    fn targets_for(&self, entry: &Path) -> Vec<PathBuf> {
+        let tab = self.tab();
+        if tab.selected.len() > 1 && tab.selected.contains(entry) {
+            tab.files.iter().filter(|file| tab.selected.contains(*file)).cloned().collect()
+        } else {
+            vec![entry.to_path_buf()]
+        }
+    }
+
+    /// Recomputes, in the background, the recursive size of every listed folder whose
+    /// cached size is missing or stale. Walks still running for a previous listing are
+    /// cancelled first.
+    fn size_folders(&mut self) -> Command<Message> {
+        self.cancel_dir_sizes();
+        if !self.show_dir_sizes {
+            return Command::none();
+        }
+        let cancel = Arc::new(AtomicBool::new(false));
+        self.dir_size_cancel = cancel.clone();
+
+        let mut commands = Vec::new();
+        let folders: Vec<PathBuf> = self
+            .tab()
+            .files
+            .iter()
+            .filter(|file| *file != Path::new("..") && self.meta_of(file).is_dir)
+            .cloned()
+            .collect();
+        for folder in &folders {
+            // Read along with the listing, so deciding what to re-walk needs no disk access
+            let mtime = self.meta_of(folder).modified;
+            if let Some(cached) = self.dir_size_cache.get_mut(folder) {
+                let too_old = cached
+                    .computed_at
+                    .elapsed()
+                    .map_or(true, |age| age > DIR_SIZE_MAX_AGE);
+                if cached.dir_mtime == mtime && !too_old {
+                    continue;
+                }
+                cached.stale = true;
+            }
+            commands.push(self.spawn_dir_size(folder.clone(), cancel.clone()));
+        }
+        Command::batch(commands)
+    }
+
+    /// Abandons every folder-size walk still running.
+    fn cancel_dir_sizes(&mut self) {
+        self.dir_size_cancel.store(true, Ordering::Relaxed);
+        self.dir_size_pending.clear();
+    }
+
+    /// Starts a background walk for the recursive size of `folder`, which lands in
+    /// `dir_size_cache` unless `cancel` is set first.
+    fn spawn_dir_size(&mut self, folder: PathBuf, cancel: Arc<AtomicBool>) -> Command<Message> {
+        let mtime = self.meta_of(&folder).modified;
+        self.dir_size_pending.insert(folder.clone());
+        Command::perform(
+            async move {
+                let size = fs_ops::dir_size(&folder, &cancel).map(|size| DirSize {
+                    size,
+                    computed_at: SystemTime::now(),
+                    dir_mtime: mtime,
+                    stale: false,
+                });
+                (folder, size)
+            },
+            |(folder, size)| Message::DirSizeComputed(folder, size),
+        )
+    }
+
+    /// Follow-up work whenever the selection changes: the image preview, and the sizes
+    /// of selected folders the status bar breakdown still lacks.
+    fn selection_changed(&mut self) -> Command<Message> {
+        let preview = self.update_preview();
+        let tab = self.tab();
+        if tab.selected.len() < 2 {
+            return preview;
+        }
+        let folders: Vec<PathBuf> = tab
+            .selected
+            .iter()
+            .filter(|entry| self.meta_of(entry).is_dir)
+            .filter(|entry| !self.dir_size_cache.contains_key(*entry))
+            .filter(|entry| !self.dir_size_pending.contains(*entry))
+            .cloned()
+            .collect();
+        if folders.is_empty() {
+            return preview;
+        }
+        // Navigating sets the flag, and size walks for the new folder get a fresh one
+        if self.dir_size_cancel.load(Ordering::Relaxed) {
+            self.dir_size_cancel = Arc::new(AtomicBool::new(false));
+        }
+        let cancel = self.dir_size_cancel.clone();
+        let mut commands = vec![preview];
+        for folder in folders {
+            commands.push(self.spawn_dir_size(folder, cancel.clone()));
+        }
+        Command::batch(commands)
+    }
+
+    /// Totals for the selection when more than one entry is selected. Folder sizes come
+    /// from `dir_size_cache`; those not measured yet are counted as such.
+    fn selection_breakdown(&self) -> Option<SelectionBreakdown> {
+        let tab = self.tab();
+        if tab.selected.len() < 2 {
+            return None;
+        }
+        let mut breakdown = SelectionBreakdown {
+            count: tab.selected.len(),
+            bytes: 0,
+            largest: None,
+            unmeasured: 0,
+        };
+        for entry in &tab.selected {
+            let meta = self.meta_of(entry);
+            let size = if meta.is_dir {
+                self.dir_size_cache.get(entry).map(|cached| cached.size)
+            } else {
+                meta.size
+            };
+            let Some(size) = size else {
+                breakdown.unmeasured += usize::from(meta.is_dir);
+                continue;
+            };
+            breakdown.bytes += size;
+            if breakdown.largest.as_ref().is_none_or(|(_, largest)| size > *largest) {
+                breakdown.largest = Some((entry.clone(), size));
+            }
+        }
+        Some(breakdown)
+    }
+
+    /// Recomputes the status bar totals over the entries the filter lets through.
+    fn refresh_summary(&mut self) {
+        let filter = &self.filter;
+        let tab = &mut self.tabs[self.active_tab];
+        let visible: Vec<PathBuf> =
+            tab.files.iter().filter(|file| filter.matches(file)).cloned().collect();
+        tab.summary = ListingSummary::of(&visible, &tab.entry_meta);
+    }
+
+    /// Starts a background count of the items in each listed folder that isn't cached yet.
+    fn count_folders(&self) -> Command<Message> {
+        if !self.show_folder_counts {
+            return Command::none();
+        }
+
+        let folders: Vec<PathBuf> = self
+            .tab()
+            .files
+            .iter()
+            .filter(|file| *file != Path::new("..") && self.meta_of(file).is_dir)
+            .filter(|file| !self.folder_count_cache.contains_key(*file))
+            .cloned()
+            .collect();
+        if folders.is_empty() {
+            return Command::none();
+        }
+
+        let dir = self.tab().path.clone();
+        Command::perform(
+            async move {
+                let counts = folders
+                    .into_iter()
+                    .map(|folder| {
+                        let count =
+                            fs::read_dir(long_path(&folder)).ok().map(|entries| entries.count());
+                        (folder, count)
+                    })
+                    .collect();
+                (dir, counts)
+            },
+            |(dir, counts)| Message::FolderCountsLoaded(dir, counts),
+        )
+    }
+
+    /// Whether the listed `entry` has gone, or stopped being a folder, since the listing
+    /// was read. `target` is what it resolves to. A broken link was listed as broken, so
+    /// it hasn't vanished; `navigate_to` explains it instead.
+    fn entry_vanished(&self, entry: &Path, target: &Path) -> bool {
+        let meta = self.meta_of(entry);
+        if entry == Path::new("..") || meta.is_dir {
+            !target.is_dir()
+        } else {
+            !target.exists() && !meta.broken_link
+        }
+    }
+
+    /// Reports that `target` is gone (or no longer a folder), then re-lists the current
+    /// folder so stale entries drop out. The banner outlives the new listing.
+    fn report_vanished(&mut self, target: &Path) -> Command<Message> {
+        let reason = if target.exists() {
+            "it is no longer a folder"
+        } else {
+            "it no longer exists"
+        };
+        self.error_message = Some(format!("Cannot open {}: {}", display_path(target), reason));
+        self.list_files()
+    }
+
+    /// Cached metadata for a listed entry; a blank (all-dash) record if there is none.
+    fn meta_of(&self, path: &Path) -> &EntryMeta {
+        self.tab().entry_meta.get(path).unwrap_or(&EntryMeta::UNKNOWN)
+    }
+    
+
+    /// Works out, for the extension prompt, what each target would be renamed to and
+    /// whether that can go ahead, so the preview and the Apply button stay current.
+    fn plan_change_extension(&mut self) {
+        self.change_ext_renames.clear();
+        let extension = self.change_ext_value.trim();
+        self.change_ext_error = if extension.trim_start_matches('.').is_empty() {
+            Some("Enter the new extension".to_string())
+        } else if extension.chars().any(std::path::is_separator) {
+            Some(format!("\"{}\" contains a path separator", extension))
+        } else {
+            self.change_ext_renames = self
+                .change_ext_targets
+                .iter()
+                .map(|file| (file.clone(), fs_ops::replace_extension(file, extension)))
+                .filter(|(from, to)| from != to)
+                .collect();
+            fs_ops::check_renames(&self.change_ext_renames).err()
+        };
+    }
+
+    /// Checks a user-typed name for a new entry in the current folder, returning it with
+    /// trailing whitespace removed. Names that would reach outside the folder are refused.
+    fn validate_entry_name(name: &str) -> Result<&str, String> {
+        let name = name.trim_end();
+        if name.is_empty() {
+            return Err("Enter a name".to_string());
+        }
+        if name.chars().any(std::path::is_separator) {
+            return Err(format!("\"{}\" contains a path separator", name));
+        }
+        if name == "." || name == ".." {
+            return Err(format!("\"{}\" is not a valid name", name));
+        }
+        Ok(name)
+    }
+}
+
+impl Application for FileExplorer {
+    type Message = Message;
+    type Executor = iced::executor::Default;
+    type Flags = Flags;
+
+    fn new(flags: Flags) -> (FileExplorer, Command<Message>) {
+        let drives = fs_ops::get_available_drives();
+        let drive_buttons = drives.iter().map(|_| button::State::new()).collect();
+        
+        let config = config::Config::load();
+        let mut explorer = FileExplorer {
+            tabs: vec![Tab {
+                path: env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
+                ..Tab::default()
+            }],
+            drives,
+            drive_buttons,
+            show_drives: false,
+            sort_ascending: true,
+            #[cfg(unix)]
+            ipc_listener: flags.ipc_listener,
+            ..FileExplorer::default()
+        };
+        // A path on the command line wins, then wherever we were last time
+        let start_path = flags
+            .start_path
+            .or_else(|| config.last_dir.clone().filter(|dir| dir.is_dir()))
+            .unwrap_or_else(|| explorer.tab().path.clone());
+        explorer.config = config;
+        explorer.refresh_bookmarks();
+        let command = explorer.navigate_to(start_path);
+        (explorer, command)
+    }
+
+    fn title(&self) -> String {
+        String::from("File Explorer")
+    }
+
+    fn should_exit(&self) -> bool {
+        self.exiting
+    }
+
+    fn update(
+        &mut self,
+        message: Self::Message,
+        clipboard: &mut iced::Clipboard,
+    ) -> Command<Message> {
+        match message {
+            Message::FileClicked(path) => {
+                // Ctrl/Shift-clicks only change the selection
+                let index = self.tab().files.iter().position(|file| *file == path);
+                if let Some(index) = index
+                    && (self.modifiers.is_command_pressed() || self.modifiers.shift)
+                {
+                    self.extend_selection(index, self.modifiers.shift);
+                    self.clicks.reset();
+                    return self.selection_changed();
+                }
+
+                // In single-click mode every click counts as a double-click
+                let threshold = Duration::from_millis(self.config.double_click_ms);
+                let is_double_click = match self.config.click_mode {
+                    ClickMode::SingleClick => true,
+                    ClickMode::DoubleClick => self.clicks.click(&path, Instant::now(), threshold),
+                };
+
+                if is_double_click {
+                    // Double-click detected, open the file
+                    if path.is_file()
+                        && let Err(err) = self.launcher.open(&path)
+                    {
+                        self.error_message =
+                            Some(format!("Failed to open {}: {}", display_path(&path), err));
+                    }
+                }
+
+                // Handle file/directory navigation
+                let target_path = self.resolve_entry(&path);
+
+                // Another program may have deleted or replaced it since we listed it
+                if self.entry_vanished(&path, &target_path) {
+                    return self.report_vanished(&target_path);
+                }
+                if target_path.is_dir() || !target_path.exists() {
+                    self.navigate_to(target_path)
+                } else {
+                    // A single click on a file just selects it
+                    if let Some(index) = index {
+                        self.select_only(index);
+                    }
+                    self.selection_changed()
+                }
+            }
+            Message::SelectPrevious | Message::SelectNext => {
+                self.move_selection(matches!(message, Message::SelectNext));
+                self.selection_changed()
+            }
+            Message::ToggleCursorSelected => {
+                if let Some(index) = self.tab().selected_index {
+                    self.extend_selection(index, false);
+                }
+                self.selection_changed()
+            }
+            Message::PreviewLoaded(path, dimensions) => {
+                // The selection may have moved on while the file was read
+                if let Some(preview) = &mut self.preview
+                    && preview.path == path
+                {
+                    preview.loaded = true;
+                    preview.dimensions = dimensions;
+                }
+                Command::none()
+            }
+            Message::OpenSelected => self.open_selected(),
+            Message::RevealInOsManager(path) => {
+                let path = self.resolve_entry(&path);
+                if let Err(err) = self.launcher.reveal(&path) {
+                    self.error_message = Some(format!(
+                        "Failed to show {} in the file manager: {}",
+                        display_path(&path),
+                        err
+                    ));
+                }
+                Command::none()
+            }
+            Message::Refresh => {
+                self.folder_count_cache.clear();
+                self.list_files()
+            }
+            Message::GoUp => match self.tab().path.parent().map(Path::to_path_buf) {
+                Some(parent) if !parent.is_dir() => self.report_vanished(&parent),
+                Some(parent) => self.navigate_to(parent),
+                None => {
+                    self.show_drives = true;
+                    Command::none()
+                }
+            },
+            Message::DriveSelected(drive_path) => {
+                if drive_path.is_dir() {
+                    self.navigate_to(drive_path)
+                } else {
+                    // An unplugged drive shouldn't stay on offer either
+                    self.drives = fs_ops::get_available_drives();
+                    self.report_vanished(&drive_path)
+                }
+            }
+            Message::GoHome => match fs_ops::home_dir() {
+                Some(home) => self.navigate_to(home),
+                None => {
+                    self.error_message =
+                        Some("Could not determine the home directory".to_string());
+                    Command::none()
+                }
+            },
+            Message::ToggleDrives => {
+                // Re-enumerate on open so newly attached drives show up
+                if !self.show_drives {
+                    self.drives = fs_ops::get_available_drives();
+                }
+                self.show_drives = !self.show_drives;
+                Command::none()
+            }
+            Message::ToggleFlatten => {
+                self.tab_mut().flatten = !self.tab().flatten;
+                self.list_files()
+            }
+            Message::FlattenLoaded(root, files, meta) => {
+                // Drop results for a folder we've since left
+                let (sort_key, ascending) = (self.sort_key, self.sort_ascending);
+                let tab = self.tab_mut();
+                if tab.flatten && root == tab.path {
+                    tab.entry_meta = meta;
+                    tab.files = files;
+                    sort_entries(&mut tab.files, &tab.entry_meta, sort_key, ascending);
+                    tab.loading = false;
+                    self.refresh_summary();
+                    self.restore_selection();
+                }
+                Command::none()
+            }
+            Message::FilesLoaded(dir, listing) => {
+                // A slow read of a folder we've since left mustn't replace the current one
+                if self.tab().flatten || dir != self.tab().path {
+                    return Command::none();
+                }
+                // A newer listing of the same folder is on its way
+                if matches!(listing, Err((io::ErrorKind::Interrupted, _))) {
+                    return Command::none();
+                }
+                self.apply_listing(listing);
+                let sizes = self.size_folders();
+                // After size_folders, which cancels any walks the selection had going
+                Command::batch(vec![self.count_folders(), sizes, self.selection_changed()])
+            }
+            Message::CycleParentEntryStyle => {
+                self.parent_entry_style = self.parent_entry_style.next();
+                self.list_files()
+            }
+            Message::ShowGoTo => {
+                self.goto_open = true;
+                self.goto_value.clear();
+                self.goto_error = None;
+                self.goto_input = text_input::State::focused();
+                Command::none()
+            }
+            Message::GoToChanged(value) => {
+                self.goto_value = value;
+                self.goto_error = None;
+                Command::none()
+            }
+            Message::GoToSubmitted => {
+                match fs_ops::expand_path(&self.goto_value, &self.tab().path) {
+                    Ok(target) => {
+                        self.goto_open = false;
+                        self.navigate_to(target)
+                    }
+                    Err(err) => {
+                        self.goto_error = Some(err);
+                        Command::none()
+                    }
+                }
+            }
+            Message::CloseDialogs => {
+                self.close_dialogs();
+                Command::none()
+            }
+            Message::DiscardAndLeave => self.discard_and_leave(),
+            Message::KeepEditing => {
+                self.pending_leave = None;
+                Command::none()
+            }
+            Message::CloseRequested => {
+                if !self.leave_blocked(Leave::Quit) {
+                    self.exiting = true;
+                }
+                Command::none()
+            }
+            Message::NavigateTo(target) => self.navigate_to(target),
+            Message::DirectoryChanged(dir, changes) => {
+                // Ignore a late report from the watch on a folder we've left
+                if dir != self.tab().path {
+                    return Command::none();
+                }
+                let tab = self.tab();
+                // The flattened view covers more than the one folder being watched
+                let patchable = !tab.flatten && !tab.loading && !tab.access_denied;
+                match changes.filter(|_| patchable) {
+                    Some(changes) => {
+                        self.apply_changes(changes);
+                        Command::batch(vec![self.count_folders(), self.size_folders()])
+                    }
+                    None => {
+                        // Keep the highlighted entry highlighted across the re-list
+                        let tab = self.tab_mut();
+                        tab.select_on_load = tab.cursor_entry().cloned();
+                        self.list_files()
+                    }
+                }
+            }
+            Message::AddBookmark => {
+                let dir = PathBuf::from(display_path(&self.tab().path));
+                if !self.config.bookmarks.contains(&dir) {
+                    self.config.bookmarks.push(dir);
+                    self.save_bookmarks();
+                }
+                Command::none()
+            }
+            Message::RemoveBookmark(dir) => {
+                self.config.bookmarks.retain(|bookmark| *bookmark != dir);
+                self.save_bookmarks();
+                Command::none()
+            }
+            Message::NewTab => {
+                // The new tab starts out in the folder we're looking at
+                let dir = self.tab().path.clone();
+                self.open_in_new_tab(dir)
+            }
+            Message::OpenSelectedInNewTab => {
+                // The listing already knows which entries are folders
+                let folder = self
+                    .tab()
+                    .cursor_entry()
+                    .filter(|entry| *entry != Path::new("..") && self.meta_of(entry).is_dir)
+                    .map(|entry| self.resolve_entry(entry));
+                let dir = folder.unwrap_or_else(|| self.tab().path.clone());
+                self.open_in_new_tab(dir)
+            }
+            Message::SelectTab(index) => self.select_tab(index),
+            Message::CloseTab(index) => self.close_tab(index),
+            Message::ReloadConfig => {
+                match config::Config::reload() {
+                    Ok(config) => {
+                        self.config = config;
+                        self.clicks.reset();
+                        self.refresh_bookmarks();
+                        self.notice = Some("Settings reloaded".to_string());
+                    }
+                    // Half-applied settings would be worse than the ones we have
+                    Err(err) => {
+                        self.error_message = Some(format!("Settings not reloaded, {}", err));
+                    }
+                }
+                Command::none()
+            }
+            Message::ToggleIconSet => {
+                self.config.icon_set = match self.config.icon_set {
+                    IconSet::Emoji => IconSet::Text,
+                    IconSet::Text => IconSet::Emoji,
+                };
+                if let Err(err) = self.config.save() {
+                    self.error_message = Some(format!("Cannot save the icon style: {}", err));
+                }
+                Command::none()
+            }
+            Message::ToggleLinkTargetStyle => {
+                self.config.link_target_style = match self.config.link_target_style {
+                    LinkTargetStyle::Raw => LinkTargetStyle::Absolute,
+                    LinkTargetStyle::Absolute => LinkTargetStyle::Raw,
+                };
+                // The panel's target was worked out in the old style
+                let style = self.config.link_target_style;
+                if let Some((target, rows)) = &mut self.properties
+                    && let Some((_, value)) = rows.iter_mut().find(|(label, _)| *label == "Target")
+                {
+                    *value = read_link_target(target, style);
+                }
+                if let Err(err) = self.config.save() {
+                    self.error_message = Some(format!("Cannot save the link style: {}", err));
+                }
+                Command::none()
+            }
+            Message::ToggleClickMode => {
+                self.config.click_mode = match self.config.click_mode {
+                    ClickMode::DoubleClick => ClickMode::SingleClick,
+                    ClickMode::SingleClick => ClickMode::DoubleClick,
+                };
+                self.clicks.reset();
+                if let Err(err) = self.config.save() {
+                    self.error_message = Some(format!("Cannot save the click mode: {}", err));
+                }
+                Command::none()
+            }
+            Message::ToggleTheme => {
+                self.config.theme = self.config.theme.toggled();
+                if let Err(err) = self.config.save() {
+                    self.error_message = Some(format!("Cannot save the theme: {}", err));
+                }
+                Command::none()
+            }
+            Message::ShowProperties(entry) => {
+                if entry == Path::new("..") {
+                    return Command::none();
+                }
+                let target = self.resolve_entry(&entry);
+                let mut rows = read_properties(&target, self.config.link_target_style);
+                rows.push(("Type", "…".to_string()));
+                rows.push(("Opens with", "…".to_string()));
+                self.properties = Some((target.clone(), rows));
+                self.properties_is_dir = self.meta_of(&entry).is_dir;
+                // Finding the type can mean running xdg-mime, so it's filled in later
+                Command::perform(
+                    async move {
+                        let file_type = launcher::file_type(&target);
+                        (target, file_type)
+                    },
+                    |(target, file_type)| Message::FileTypeLoaded(target, file_type),
+                )
+            }
+            Message::FileTypeLoaded(target, file_type) => {
+                // The panel may have moved on to another entry meanwhile
+                if let Some((shown, rows)) = &mut self.properties
+                    && *shown == target
+                {
+                    let unknown = || "unknown".to_string();
+                    let apps = Some(file_type.apps.join(", ")).filter(|apps| !apps.is_empty());
+                    for (label, value) in rows.iter_mut() {
+                        match *label {
+                            "Type" => *value = file_type.mime.clone().unwrap_or_else(unknown),
+                            "Opens with" => *value = apps.clone().unwrap_or_else(unknown),
+                            _ => {}
+                        }
+                    }
+                }
+                Command::none()
+            }
+            Message::RunCustomAction(index, path) => {
+                let Some(action) = self.config.custom_actions.get(index) else {
+                    return Command::none();
+                };
+                let (label, command) = (action.label.clone(), action.command_line(&path));
+                let dir = path.parent().unwrap_or(&path).to_path_buf();
+                self.notice = Some(format!("Running {}…", label));
+                Command::perform(
+                    async move {
+                        let result = launcher::run_custom_action(&command, &dir);
+                        (label, result)
+                    },
+                    |(label, result)| Message::CustomActionFinished(label, result),
+                )
+            }
+            Message::SetTag(path, tag) => {
+                match tag {
+                    Some(tag) => self.config.tags.insert(path, tag),
+                    None => self.config.tags.remove(&path),
+                };
+                if let Err(err) = self.config.save() {
+                    self.error_message = Some(format!("Cannot save the tag: {}", err));
+                }
+                Command::none()
+            }
+            Message::CustomActionFinished(label, result) => {
+                match result {
+                    // The last line is usually the one that sums up what happened
+                    Ok(output) => {
+                        self.notice = Some(match output.lines().last() {
+                            Some(line) => format!("{} finished: {}", label, line),
+                            None => format!("{} finished", label),
+                        });
+                    }
+                    Err(err) => self.error_message = Some(format!("{} failed: {}", label, err)),
+                }
+                // The action may well have written files here
+                self.list_files()
+            }
+            Message::CopySelectionBreakdown => {
+                if let Some(breakdown) = self.selection_breakdown() {
+                    clipboard.write(breakdown.report());
+                    self.notice = Some("Copied the size breakdown".to_string());
+                }
+                Command::none()
+            }
+            Message::CopyPath(entry) => {
+                let paths: Vec<String> = self
+                    .targets_for(&entry)
+                    .iter()
+                    .map(|entry| {
+                        let target = self.resolve_entry(entry);
+                        let target = std::path::absolute(&target).unwrap_or(target);
+                        // Lossy so a non-UTF-8 name still copies, with U+FFFD for bad bytes
+                        display_path(&target)
+                    })
+                    .collect();
+                self.notice = Some(match paths.as_slice() {
+                    [path] => format!("Copied {}", path),
+                    _ => format!("Copied {} paths", paths.len()),
+                });
+                // One path per line, which is also what Paste reads back
+                clipboard.write(paths.join("\n"));
+                Command::none()
+            }
+            Message::ModifiersChanged(modifiers) => {
+                let alt_changed = modifiers.alt != self.modifiers.alt;
+                self.modifiers = modifiers;
+                if !alt_changed || self.show_hidden {
+                    return Command::none();
+                }
+                let tab = self.tab_mut();
+                tab.select_on_load = tab.cursor_entry().cloned();
+                self.list_files()
+            }
+            // Nothing to change; the message just gets the rows in view rebuilt
+            Message::ListScrolled => Command::none(),
+            Message::ToggleHidden => {
+                self.show_hidden = !self.show_hidden;
+                self.list_files()
+            }
+            Message::ToggleFolderCounts => {
+                self.show_folder_counts = !self.show_folder_counts;
+                self.count_folders()
+            }
+            Message::FolderCountsLoaded(dir, counts) => {
+                // Drop results for a folder we've since left
+                if dir == self.tab().path {
+                    self.folder_count_cache.extend(counts);
+                }
+                Command::none()
+            }
+            Message::ToggleDirSizes => {
+                self.show_dir_sizes = !self.show_dir_sizes;
+                self.size_folders()
+            }
+            Message::DirSizeComputed(folder, size) => {
+                self.dir_size_pending.remove(&folder);
+                // `None` means the walk was cancelled because we navigated away
+                if let Some(size) = size {
+                    self.dir_size_cache.insert(folder, size);
+                }
+                Command::none()
+            }
+            Message::ShowSiblings => {
+                if self.show_siblings {
+                    self.show_siblings = false;
+                } else if self.tab().path.parent().is_some() {
+                    // Shown once the parent has been read, which can be slow on a network drive
+                    let dir = self.tab().path.clone();
+                    return Command::perform(
+                        async move {
+                            let siblings = fs_ops::sibling_folders(&dir);
+                            (dir, siblings)
+                        },
+                        |(dir, siblings)| Message::SiblingsLoaded(dir, siblings),
+                    );
+                } else {
+                    // A drive root has no siblings, but the other drives are the next best thing
+                    self.show_drives = true;
+                }
+                Command::none()
+            }
+            Message::SiblingsLoaded(dir, siblings) => {
+                // Not if we've moved on meanwhile
+                if dir == self.tab().path {
+                    self.siblings = siblings;
+                    self.show_siblings = true;
+                }
+                Command::none()
+            }
+            Message::AddressChanged(value) => {
+                self.address_value = value;
+                self.address_error = None;
+                Command::none()
+            }
+            Message::AddressSubmitted => self.submit_address(),
+            Message::SortBy(key) => {
+                // Clicking the active column flips it; a new column starts ascending
+                if self.sort_key == key {
+                    self.sort_ascending = !self.sort_ascending;
+                } else {
+                    self.sort_key = key;
+                    self.sort_ascending = true;
+                }
+                self.list_files()
+            }
+            Message::NewFolder => {
+                self.new_folder_open = true;
+                self.new_folder_name.clear();
+                self.new_folder_error = None;
+                self.new_folder_input = text_input::State::focused();
+                Command::none()
+            }
+            Message::CompareFiles(a, b) => {
+                let (a, b) = (self.resolve_entry(&a), self.resolve_entry(&b));
+                match self.launcher.compare(&a, &b) {
+                    Ok(()) => Command::none(),
+                    Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                        // No diff tool, so compare them here; reading waits for the background
+                        self.diff_view = Some(DiffView {
+                            a: a.clone(),
+                            b: b.clone(),
+                            lines: None,
+                            scroll: scrollable::State::new(),
+                            close_button: button::State::new(),
+                        });
+                        Command::perform(
+                            async move {
+                                let lines = diff::diff_files(&a, &b);
+                                (a, b, lines)
+                            },
+                            |(a, b, lines)| Message::DiffLoaded(a, b, lines),
+                        )
+                    }
+                    Err(err) => {
+                        self.error_message = Some(format!(
+                            "Cannot compare {} and {}: {}",
+                            display_path(&a),
+                            display_path(&b),
+                            err
+                        ));
+                        Command::none()
+                    }
+                }
+            }
+            Message::DiffLoaded(a, b, lines) => {
+                // The view may have been closed or moved to another pair meanwhile
+                if let Some(view) = &mut self.diff_view
+                    && view.a == a
+                    && view.b == b
+                {
+                    view.lines = Some(lines);
+                }
+                Command::none()
+            }
+            Message::ChangeExtension => {
+                let tab = self.tab();
+                self.change_ext_targets = tab
+                    .files
+                    .iter()
+                    .filter(|file| tab.selected.contains(*file) && !self.meta_of(file).is_dir)
+                    .map(|file| fs_ops::resolve_entry(&tab.path, file))
+                    .collect();
+                if self.change_ext_targets.is_empty() {
+                    self.error_message =
+                        Some("Select the files whose extension should change".to_string());
+                    return Command::none();
+                }
+                self.change_ext_value.clear();
+                self.change_ext_input = text_input::State::focused();
+                self.plan_change_extension();
+                Command::none()
+            }
+            Message::ChangeExtensionChanged(value) => {
+                self.change_ext_value = value;
+                self.plan_change_extension();
+                Command::none()
+            }
+            Message::ApplyChangeExtension => {
+                if self.change_ext_error.is_some() || self.change_ext_renames.is_empty() {
+                    return Command::none();
+                }
+                if let Err(err) = fs_ops::rename_all(&self.change_ext_renames) {
+                    self.change_ext_error = Some(format!("Nothing was renamed. {}", err));
+                    return Command::none();
+                }
+                let renamed = std::mem::take(&mut self.change_ext_renames);
+                self.change_ext_targets.clear();
+                self.notice = Some(format!("Renamed {} files", renamed.len()));
+                let command = self.list_files();
+                let tab = self.tab_mut();
+                tab.selected = renamed.into_iter().map(|(_, to)| to).collect();
+                command
+            }
+            Message::NewFolderNameChanged(name) => {
+                self.new_folder_name = name;
+                self.new_folder_error = None;
+                Command::none()
+            }
+            Message::CreateFolder => {
+                let name = match FileExplorer::validate_entry_name(&self.new_folder_name) {
+                    Ok(name) => name.to_string(),
+                    Err(err) => {
+                        self.new_folder_error = Some(err);
+                        return Command::none();
+                    }
+                };
+                let folder = self.tab().path.join(&name);
+                if let Err(err) = fs::create_dir(&folder) {
+                    self.new_folder_error = Some(format!("Cannot create \"{}\": {}", name, err));
+                    return Command::none();
+                }
+
+                self.new_folder_open = false;
+                let command = self.list_files();
+                // Highlight the new folder so it's easy to spot after sorting
+                self.tab_mut().select_on_load = Some(folder);
+                command
+            }
+            Message::Delete(target) => {
+                // `..` is a navigation shortcut, not something that can be deleted
+                if target != Path::new("..") {
+                    self.pending_delete = self.targets_for(&target);
+                }
+                Command::none()
+            }
+            Message::ConfirmDelete => {
+                if self.pending_delete.is_empty() {
+                    return Command::none();
+                }
+                if self.delete_job.is_some() {
+                    self.error_message =
+                        Some("Wait for the current delete to finish or cancel it".to_string());
+                    return Command::none();
+                }
+                let targets = std::mem::take(&mut self.pending_delete);
+                let cancel = Arc::new(AtomicBool::new(false));
+                self.delete_job = Some(DeleteJob {
+                    items: None,
+                    done: 0,
+                    deleted: 0,
+                    kept: 0,
+                    failures: Vec::new(),
+                    blocked: HashSet::new(),
+                    cancel: cancel.clone(),
+                });
+                Command::perform(
+                    async move { fs_ops::plan_delete(&targets, &cancel) },
+                    Message::DeletePlanned,
+                )
+            }
+            Message::DeletePlanned(items) => {
+                if let Some(job) = &mut self.delete_job {
+                    job.items = Some(items);
+                }
+                self.next_delete_batch()
+            }
+            Message::DeleteProgress(outcomes, blocked) => {
+                let Some(job) = &mut self.delete_job else {
+                    return Command::none();
+                };
+                job.blocked = blocked;
+                job.done += outcomes.len();
+                for (path, outcome) in outcomes {
+                    match outcome {
+                        DeleteOutcome::Deleted => job.deleted += 1,
+                        DeleteOutcome::Kept => job.kept += 1,
+                        DeleteOutcome::Failed(err) => {
+                            job.failures.push(format!("{}: {}", display_path(&path), err));
+                        }
+                    }
+                }
+                self.next_delete_batch()
+            }
+            Message::CancelDeleteJob => {
+                if let Some(job) = &self.delete_job {
+                    job.cancel.store(true, Ordering::Relaxed);
+                }
+                Command::none()
+            }
+            Message::StartRename(target) => {
+                if target != Path::new("..") {
+                    // Edit the full name, extension included, so it can't be dropped by accident
+                    self.rename_value = target
+                        .file_name()
+                        .map(|name| name.to_string_lossy().into_owned())
+                        .unwrap_or_default();
+                    self.rename_error = None;
+                    self.rename_input = text_input::State::focused();
+                    self.rename_input.move_cursor_to_end();
+                    self.renaming = Some(target);
+                }
+                Command::none()
+            }
+            Message::RenameChanged(value) => {
+                self.rename_value = value;
+                self.rename_error = None;
+                Command::none()
+            }
+            Message::CommitRename(new_name) => {
+                let Some(source) = self.renaming.clone() else {
+                    return Command::none();
+                };
+                let new_name = match FileExplorer::validate_entry_name(&new_name) {
+                    Ok(name) => name.to_string(),
+                    Err(err) => {
+                        self.rename_error = Some(err);
+                        return Command::none();
+                    }
+                };
+                let old_name = source.file_name().map(|name| name.to_string_lossy());
+                if old_name.as_deref() == Some(new_name.as_str()) {
+                    self.renaming = None;
+                    return Command::none();
+                }
+
+                let dest = source.with_file_name(&new_name);
+                // A case-only rename finds the entry itself on case-insensitive filesystems
+                if fs::symlink_metadata(&dest).is_ok() && !fs_ops::same_entry(&source, &dest) {
+                    self.rename_error = Some(format!("\"{}\" already exists", new_name));
+                    return Command::none();
+                }
+                if let Err(err) = fs::rename(&source, &dest) {
+                    self.rename_error = Some(format!("Cannot rename: {}", err));
+                    return Command::none();
+                }
+
+                self.renaming = None;
+                // The tag belongs to the entry, not the name
+                if let Some(tag) = self.config.tags.remove(&source) {
+                    self.config.tags.insert(dest.clone(), tag);
+                    if let Err(err) = self.config.save() {
+                        self.error_message = Some(format!("Cannot save the tag: {}", err));
+                    }
+                }
+                // Re-list so the entry moves to where the new name sorts
+                let command = self.list_files();
+                self.tab_mut().select_on_load = Some(dest);
+                command
+            }
+            Message::Back | Message::Forward => {
+                let tab = self.tab();
+                let index = if matches!(message, Message::Back) {
+                    tab.history_index.checked_sub(1)
+                } else {
+                    Some(tab.history_index + 1)
+                };
+                match index {
+                    Some(index) => self.go_to_history(index),
+                    None => Command::none(),
+                }
+            }
+            Message::FilterChanged(text) => {
+                // Only the view narrows; `files` keeps the full listing
+                self.filter.set(text, self.filter.mode);
+                self.refresh_summary();
+                Command::none()
+            }
+            Message::ToggleFilterMode => {
+                let mode = match self.filter.mode {
+                    FilterMode::Contains => FilterMode::Glob,
+                    FilterMode::Glob => FilterMode::Contains,
+                };
+                let text = std::mem::take(&mut self.filter.text);
+                self.filter.set(text, mode);
+                self.refresh_summary();
+                Command::none()
+            }
+            Message::DismissError => {
+                self.error_message = None;
+                Command::none()
+            }
+            Message::CopySelected | Message::CutSelected => {
+                let op = if matches!(message, Message::CutSelected) {
+                    ClipOp::Cut
+                } else {
+                    ClipOp::Copy
+                };
+                let tab = self.tab();
+                let sources: Vec<PathBuf> =
+                    tab.files.iter().filter(|file| tab.selected.contains(*file)).cloned().collect();
+                if !sources.is_empty() {
+                    let verb = if op == ClipOp::Cut { "cut" } else { "copied" };
+                    self.notice = Some(format!(
+                        "{} item(s) {}; paste them into any folder",
+                        sources.len(),
+                        verb
+                    ));
+                    self.clipboard_op = Some((sources, op));
+                }
+                Command::none()
+            }
+            Message::Paste => {
+                // Nothing copied inside the app: take paths from the OS clipboard instead
+                if self.clipboard_op.is_none() {
+                    return self.update(Message::PasteFromOS, clipboard);
+                }
+                let dest = self.tab().path.clone();
+                self.update(Message::PasteTo(dest), clipboard)
+            }
+            Message::PasteTo(dest) => {
+                let Some((sources, op)) = self.clipboard_op.clone() else {
+                    return Command::none();
+                };
+                self.start_paste(sources, dest, op, "Paste", true)
+            }
+            Message::PastePlanned(plan) => {
+                let Some(job) = &mut self.paste_job else {
+                    return Command::none();
+                };
+                match plan {
+                    Ok(plan) => {
+                        job.steps = Some(plan.steps);
+                        job.progress = plan.progress;
+                        // Cut entries are on their way, so pasting them again would find nothing
+                        let pasted = Some((job.sources.clone(), ClipOp::Cut));
+                        if job.op == ClipOp::Cut && self.clipboard_op == pasted {
+                            self.clipboard_op = None;
+                        }
+                        self.next_paste_batch()
+                    }
+                    Err(err) => {
+                        self.error_message =
+                            Some(format!("Cannot {}: {}", job.action.to_lowercase(), err));
+                        self.paste_job = None;
+                        Command::none()
+                    }
+                }
+            }
+            Message::PasteProgress(done, progress) => {
+                let Some(job) = &mut self.paste_job else {
+                    return Command::none();
+                };
+                job.done += done;
+                job.progress = progress;
+                self.next_paste_batch()
+            }
+            Message::CancelPasteJob => {
+                if let Some(job) = &self.paste_job {
+                    job.cancel.store(true, Ordering::Relaxed);
+                }
+                Command::none()
+            }
+            Message::PasteFromOS => {
+                let sources = clipboard
+                    .read()
+                    .map(|text| fs_ops::parse_clipboard_paths(&text))
+                    .unwrap_or_default();
+                if sources.is_empty() {
+                    self.error_message =
+                        Some("Clipboard does not contain any file paths".to_string());
+                    return Command::none();
+                }
+
+                let dest = self.tab().path.clone();
+                self.start_paste(sources, dest, ClipOp::Copy, "Paste", false)
+            }
+            Message::FileDropped(path) => {
+                let now = Instant::now();
+                let same_drop = self
+                    .last_drop
+                    .is_some_and(|last| now.duration_since(last) < DROP_BATCH_WINDOW);
+                self.last_drop = Some(now);
+
+                // Once part of a drop is up for copying, the rest of it joins in
+                if same_drop && !self.pending_drop.is_empty() {
+                    self.pending_drop.push(path);
+                    return Command::none();
+                }
+                if path.is_dir() {
+                    // Only one folder can be opened; the first of the drop wins
+                    if same_drop {
+                        self.notice = Some("Only the first dropped folder was opened".to_string());
+                        return Command::none();
+                    }
+                    return self.navigate_to(path);
+                }
+                if let Some(index) = self.tab().files.iter().position(|file| *file == path) {
+                    // Already listed here: select it, along with the rest of the drop
+                    if same_drop {
+                        self.tab_mut().selected.insert(path);
+                    } else {
+                        self.select_only(index);
+                    }
+                    self.scroll_to_row(index);
+                    return self.selection_changed();
+                }
+                if !path.exists() {
+                    self.error_message =
+                        Some(format!("Cannot use {}: it no longer exists", display_path(&path)));
+                    return Command::none();
+                }
+                // From somewhere else: offer to copy it into the folder we're in
+                self.pending_drop.push(path);
+                Command::none()
+            }
+            Message::TypeAhead(c) => {
+                // Ctrl/Cmd combinations are shortcuts, not names
+                if self.modifiers.is_command_pressed() || self.modifiers.alt {
+                    return Command::none();
+                }
+                self.jump_to_prefix(c)
+            }
+            Message::ConfirmDrop => {
+                let sources = std::mem::take(&mut self.pending_drop);
+                let dest = self.tab().path.clone();
+                self.start_paste(sources, dest, ClipOp::Copy, "Copy", false)
+            }
+        }
+    }
+
+    fn subscription(&self) -> Subscription<Message> {
+        let mut subscriptions = vec![
+            iced_native::subscription::events_with(shortcut_for),
+            // Keyed by path, so navigating drops the old watch and starts a new one
+            Subscription::from_recipe(watch::Watch(self.tab().path.clone())),
+        ];
+
+        #[cfg(unix)]
+        if let Some(listener) = &self.ipc_listener {
+            subscriptions.push(Subscription::from_recipe(ipc::Listen(listener.clone())));
+        }
+
+        Subscription::batch(subscriptions)
+    }
+
+    fn view(&mut self) -> Element<'_, Message> {
+        let theme = self.config.theme;
+        let icon_set = self.config.icon_set;
+        let breakdown = self.selection_breakdown();
+        let held_back = self.unsaved_input().filter(|_| self.pending_leave.is_some());
+        // Read before the active tab is borrowed for its widgets
+        let tab_names: Vec<String> = self
+            .tabs
+            .iter()
+            .map(|tab| {
+                tab.path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| display_path(&tab.path))
+            })
+            .collect();
+        let tab = &mut self.tabs[self.active_tab];
+        // Main column with spacing and padding
+        let mut column = Column::new().spacing(10).padding(10);
+
+        // Error banner
+        if let Some(err) = &self.error_message {
+            column = column.push(
+                Container::new(
+                    Row::new()
+                        .spacing(10)
+                        .align_items(Align::Center)
+                        .push(Text::new(err.as_str()).width(Length::Fill))
+                        .push(
+                            themed_button(
+                                theme,
+                                &mut self.dismiss_error_button,
+                                Text::new("Dismiss"),
+                            )
+                            .on_press(Message::DismissError)
+                            .padding(5),
+                        ),
+                )
+                .width(Length::Fill)
+                .padding(8)
+                .style(ErrorBanner(theme)),
+            );
+        }
+
+        // 1. Show current directory at top, editable to jump elsewhere
+        column = column.push(
+            Row::new()
+                .spacing(10)
+                .push(Text::new("Directory:").size(16))
+                .push(
+                    TextInput::new(
+                        &mut self.address_input,
+                        "Type or paste a path",
+                        &self.address_value,
+                        Message::AddressChanged,
+                    )
+                    .style(InputStyle(theme))
+                    .on_submit(Message::AddressSubmitted)
+                    .size(16)
+                    .padding(5),
+                ),
+        );
+        if let Some(err) = &self.address_error {
+            column = column.push(Text::new(err.as_str()).size(14));
+        }
+
+        // Breadcrumbs: one button per ancestor of the current folder
+        let crumbs = breadcrumbs(&tab.path);
+        self.breadcrumb_buttons.resize_with(crumbs.len(), button::State::new);
+        // Rows don't wrap, so deep paths keep the root and the last few folders
+        let elided = crumbs.len().saturating_sub(BREADCRUMB_MAX);
+        let mut crumb_row = Row::new().spacing(5).align_items(Align::Center);
+        for (index, ((label, target), btn_state)) in
+            crumbs.into_iter().zip(self.breadcrumb_buttons.iter_mut()).enumerate()
+        {
+            if elided > 0 && index == 1 {
+                crumb_row = crumb_row.push(Text::new("…").size(14));
+            }
+            if elided > 0 && (1..=elided).contains(&index) {
+                continue;
+            }
+            if index > 0 {
+                crumb_row = crumb_row.push(Text::new("›").size(14));
+            }
+            let mut crumb = themed_button(theme, btn_state, Text::new(label).size(14)).padding(3);
+            // The last crumb is the folder we're in
+            if target != tab.path {
+                crumb = crumb.on_press(Message::NavigateTo(target));
+            }
+            crumb_row = crumb_row.push(crumb);
+        }
+        column = column.push(crumb_row);
+
+        // Top buttons row
+        let mut top_row = Row::new().spacing(10);
+        // Without on_press the buttons render disabled when there's nowhere to go
+        let mut back = themed_button(theme, &mut self.back_button, Text::new("Back")).padding(5);
+        if tab.history_index > 0 {
+            back = back.on_press(Message::Back);
+        }
+        let mut forward =
+            themed_button(theme, &mut self.forward_button, Text::new("Forward")).padding(5);
+        if tab.history_index + 1 < tab.history.len() {
+            forward = forward.on_press(Message::Forward);
+        }
+        top_row = top_row.push(back).push(forward);
+        top_row = top_row.push(
+            themed_button(theme, &mut self.refresh_button, Text::new("Refresh"))
+                .on_press(Message::Refresh)
+                .padding(5),
+        );
+        top_row = top_row.push(
+            themed_button(theme, &mut self.up_button, Text::new("Go Up"))
+                .on_press(Message::GoUp)
+                .padding(5),
+        );
+        top_row = top_row.push(
+            themed_button(theme, &mut self.home_button, Text::new("Home"))
+                .on_press(Message::GoHome)
+                .padding(5),
+        );
+        let mut bookmark =
+            themed_button(theme, &mut self.bookmark_button, Text::new("Bookmark")).padding(5);
+        if !self.config.bookmarks.contains(&PathBuf::from(display_path(&tab.path))) {
+            bookmark = bookmark.on_press(Message::AddBookmark);
+        }
+        top_row = top_row.push(bookmark);
+        top_row = top_row.push(
+            themed_button(
+                theme,
+                &mut self.theme_button,
+                Text::new(if theme == Theme::Dark { "Light theme" } else { "Dark theme" }),
+            )
+            .on_press(Message::ToggleTheme)
+            .padding(5),
+        );
+        let click_label = match self.config.click_mode {
+            ClickMode::DoubleClick => "Single-click to open",
+            ClickMode::SingleClick => "Double-click to open",
+        };
+        top_row = top_row.push(
+            themed_button(theme, &mut self.click_mode_button, Text::new(click_label))
+                .on_press(Message::ToggleClickMode)
+                .padding(5),
+        );
+        let icon_label = match self.config.icon_set {
+            IconSet::Emoji => "Text icons",
+            IconSet::Text => "Emoji icons",
+        };
+        top_row = top_row.push(
+            themed_button(theme, &mut self.icon_set_button, Text::new(icon_label))
+                .on_press(Message::ToggleIconSet)
+                .padding(5),
+        );
+        let link_label = match self.config.link_target_style {
+            LinkTargetStyle::Raw => "Absolute link targets",
+            LinkTargetStyle::Absolute => "Raw link targets",
+        };
+        top_row = top_row.push(
+            themed_button(theme, &mut self.link_style_button, Text::new(link_label))
+                .on_press(Message::ToggleLinkTargetStyle)
+                .padding(5),
+        );
+        top_row = top_row.push(
+            themed_button(theme, &mut self.new_folder_button, Text::new("New Folder"))
+                .on_press(Message::NewFolder)
+                .padding(5),
+        );
+        top_row = top_row.push(
+            themed_button(
+                theme,
+                &mut self.flatten_button,
+                Text::new(if tab.flatten { "Unflatten" } else { "Flatten" }),
+            )
+            .on_press(Message::ToggleFlatten)
+            .padding(5),
+        );
+        top_row = top_row.push(
+            themed_button(theme, &mut self.paste_button, Text::new("Paste"))
+                .on_press(Message::PasteFromOS)
+                .padding(5),
+        );
+        top_row = top_row.push(
+            themed_button(
+                theme,
+                &mut self.parent_style_button,
+                Text::new(match self.parent_entry_style {
+                    ParentEntryStyle::Parent => "Parent: ..",
+                    ParentEntryStyle::UpRow => "Parent: ⬆",
+                    ParentEntryStyle::Hidden => "Parent: hidden",
+                }),
+            )
+            .on_press(Message::CycleParentEntryStyle)
+            .padding(5),
+        );
+        top_row = top_row.push(
+            themed_button(
+                theme,
+                &mut self.hidden_button,
+                Text::new(if self.show_hidden { "Hide hidden" } else { "Show hidden" }),
+            )
+            .on_press(Message::ToggleHidden)
+            .padding(5),
+        );
+        top_row = top_row.push(
+            themed_button(
+                theme,
+                &mut self.folder_counts_button,
+                Text::new(if self.show_folder_counts { "Hide counts" } else { "Show counts" }),
+            )
+            .on_press(Message::ToggleFolderCounts)
+            .padding(5),
+        );
+        top_row = top_row.push(
+            themed_button(
+                theme,
+                &mut self.dir_sizes_button,
+                Text::new(if self.show_dir_sizes { "Hide sizes" } else { "Folder sizes" }),
+            )
+            .on_press(Message::ToggleDirSizes)
+            .padding(5),
+        );
+        top_row = top_row.push(
+            themed_button(theme, &mut self.siblings_button, Text::new("Siblings"))
+                .on_press(Message::ShowSiblings)
+                .padding(5),
+        );
+        
+        // Drive button, revealing the drive picker below
+        top_row = top_row.push(
+            themed_button(theme, &mut self.drive_button, Text::new("Drives"))
+                .on_press(Message::ToggleDrives)
+                .padding(5),
+        );
+        
+        column = column.push(top_row);
+
+        // File operations on the selection
+        let mut edit_row = Row::new().spacing(10).align_items(Align::Center);
+        let has_targets = !tab.selected.is_empty();
+        let mut copy = themed_button(theme, &mut self.copy_button, Text::new("Copy")).padding(5);
+        let mut cut = themed_button(theme, &mut self.cut_button, Text::new("Cut")).padding(5);
+        if has_targets {
+            copy = copy.on_press(Message::CopySelected);
+            cut = cut.on_press(Message::CutSelected);
+        }
+        let paste_label = match &self.clipboard_op {
+            Some((sources, ClipOp::Copy)) => format!("Paste here ({} copied)", sources.len()),
+            Some((sources, ClipOp::Cut)) => format!("Paste here ({} cut)", sources.len()),
+            None => "Paste here".to_string(),
+        };
+        let mut paste_here =
+            themed_button(theme, &mut self.paste_here_button, Text::new(paste_label)).padding(5);
+        if self.clipboard_op.is_some() {
+            paste_here = paste_here.on_press(Message::Paste);
+        }
+        let mut change_ext =
+            themed_button(theme, &mut self.change_ext_button, Text::new("Change extension…"))
+                .padding(5);
+        if has_targets {
+            change_ext = change_ext.on_press(Message::ChangeExtension);
+        }
+        let mut compare =
+            themed_button(theme, &mut self.compare_button, Text::new("Compare")).padding(5);
+        // Only a pair of files, taken in list order
+        let pair: Vec<&PathBuf> =
+            tab.files.iter().filter(|file| tab.selected.contains(*file)).collect();
+        let is_file = |path: &PathBuf| tab.entry_meta.get(path).is_some_and(|meta| !meta.is_dir);
+        if let [a, b] = pair[..]
+            && is_file(a)
+            && is_file(b)
+        {
+            compare = compare.on_press(Message::CompareFiles(a.clone(), b.clone()));
+        }
+        edit_row = edit_row.push(copy).push(cut).push(paste_here);
+        // Recent destinations, so pasting far away doesn't mean going there first
+        if self.clipboard_op.is_some() {
+            let here = PathBuf::from(display_path(&tab.path));
+            let destinations: Vec<&PathBuf> =
+                self.config.recent_destinations.iter().filter(|dir| **dir != here).collect();
+            self.paste_to_buttons.resize_with(destinations.len(), button::State::new);
+            for (dest, state) in destinations.into_iter().zip(&mut self.paste_to_buttons) {
+                let name = dest.file_name().map_or_else(
+                    || display_path(dest),
+                    |name| name.to_string_lossy().into_owned(),
+                );
+                edit_row = edit_row.push(
+                    themed_button(theme, state, Text::new(format!("→ {}", name)).size(14))
+                        .on_press(Message::PasteTo(dest.clone()))
+                        .padding(5),
+                );
+            }
+        }
+        edit_row = edit_row.push(change_ext).push(compare);
+        column = column.push(edit_row);
+
+        // In-app comparison
+        if let Some(view) = &mut self.diff_view {
+            // Plenty to review by eye; a real diff tool is better past this
+            const SHOWN_LINES: usize = 500;
+            let title = format!("{} ↔ {}", display_path(&view.a), display_path(&view.b));
+            column = column.push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Align::Center)
+                    .push(Text::new(title).size(16))
+                    .push(
+                        themed_button(theme, &mut view.close_button, Text::new("Close"))
+                            .on_press(Message::CloseDialogs)
+                            .padding(5),
+                    ),
+            );
+            match &view.lines {
+                None => column = column.push(Text::new("Comparing…").size(14)),
+                Some(Err(err)) => column = column.push(Text::new(err.as_str()).size(14)),
+                Some(Ok(lines)) if lines.iter().all(|line| matches!(line, diff::Line::Same(_))) => {
+                    column = column.push(Text::new("The files are identical").size(14));
+                }
+                Some(Ok(lines)) => {
+                    let mut shown = Scrollable::new(&mut view.scroll).height(Length::Units(200));
+                    for line in lines.iter().take(SHOWN_LINES) {
+                        let (marker, text, color) = match line {
+                            diff::Line::Same(text) => ("  ", text, theme.text()),
+                            diff::Line::Removed(text) => ("- ", text, theme.removed()),
+                            diff::Line::Added(text) => ("+ ", text, theme.added()),
+                        };
+                        shown = shown
+                            .push(Text::new(format!("{}{}", marker, text)).size(14).color(color));
+                    }
+                    let more = lines.len().saturating_sub(SHOWN_LINES);
+                    if more > 0 {
+                        shown = shown.push(
+                            Text::new(format!("and {} more lines", more))
+                                .size(14)
+                                .color(theme.dimmed()),
+                        );
+                    }
+                    column = column.push(shown);
+                }
+            }
+        }
+
+        // Extension prompt, with a preview of the renames
+        if !self.change_ext_targets.is_empty() {
+            let mut apply = themed_button(
+                theme,
+                &mut self.apply_change_ext_button,
+                Text::new("Apply"),
+            )
+            .padding(5);
+            if self.change_ext_error.is_none() && !self.change_ext_renames.is_empty() {
+                apply = apply.on_press(Message::ApplyChangeExtension);
+            }
+            column = column.push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Align::Center)
+                    .push(Text::new(format!(
+                        "New extension for {} files:",
+                        self.change_ext_targets.len()
+                    )))
+                    .push(
+                        TextInput::new(
+                            &mut self.change_ext_input,
+                            ".md",
+                            &self.change_ext_value,
+                            Message::ChangeExtensionChanged,
+                        )
+                        .style(InputStyle(theme))
+                        .on_submit(Message::ApplyChangeExtension)
+                        .padding(5)
+                        .width(Length::Units(120)),
+                    )
+                    .push(apply),
+            );
+            if let Some(err) = &self.change_ext_error {
+                column = column.push(Text::new(err.as_str()).size(14));
+            } else {
+                // Enough to check the pattern is right without pushing the list off screen
+                const PREVIEW_ROWS: usize = 5;
+                let name = |path: &Path| {
+                    path.file_name().map(|name| name.to_string_lossy().into_owned())
+                };
+                for (from, to) in self.change_ext_renames.iter().take(PREVIEW_ROWS) {
+                    let (from, to) = (name(from).unwrap_or_default(), name(to).unwrap_or_default());
+                    column = column.push(Text::new(format!("{} → {}", from, to)).size(14));
+                }
+                let more = self.change_ext_renames.len().saturating_sub(PREVIEW_ROWS);
+                if more > 0 {
+                    column = column.push(Text::new(format!("and {} more", more)).size(14));
+                }
+            }
+        }
+
+        // Leaving was held back because it would throw away what a dialog holds
+        if let Some(what) = held_back {
+            column = column.push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Align::Center)
+                    .push(Text::new(format!("Discard the unfinished {}?", what)))
+                    .push(
+                        themed_button(theme, &mut self.discard_button, Text::new("Discard"))
+                            .on_press(Message::DiscardAndLeave)
+                            .padding(5),
+                    )
+                    .push(
+                        themed_button(
+                            theme,
+                            &mut self.keep_editing_button,
+                            Text::new("Keep editing"),
+                        )
+                        .on_press(Message::KeepEditing)
+                        .padding(5),
+                    ),
+            );
+        }
+
+        // Delete confirmation
+        if !self.pending_delete.is_empty() {
+            let question = match self.pending_delete.as_slice() {
+                [target] => {
+                    let name = target
+                        .file_name()
+                        .map(|name| name.to_string_lossy().into_owned())
+                        .unwrap_or_else(|| display_path(target));
+                    // A link to a folder is removed by itself, leaving the folder be
+                    let meta = tab.entry_meta.get(target).unwrap_or(&EntryMeta::UNKNOWN);
+                    if meta.is_dir && !meta.is_symlink {
+                        format!("Delete the folder \"{}\" and everything inside it?", name)
+                    } else {
+                        format!("Delete the file \"{}\"?", name)
+                    }
+                }
+                targets => format!(
+                    "Delete {} selected items? Folders go with everything inside them.",
+                    targets.len()
+                ),
+            };
+            column = column.push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Align::Center)
+                    .push(Text::new(question))
+                    .push(
+                        themed_button(theme, &mut self.confirm_delete_button, Text::new("Delete"))
+                            .on_press(Message::ConfirmDelete)
+                            .padding(5),
+                    )
+                    .push(
+                        themed_button(theme, &mut self.cancel_delete_button, Text::new("Cancel"))
+                            .on_press(Message::CloseDialogs)
+                            .padding(5),
+                    ),
+            );
+        }
+
+        // Progress of a delete running in the background
+        if let Some(job) = &self.delete_job {
+            let progress = match &job.items {
+                None => "Finding what to delete…".to_string(),
+                Some(items) => format!("Deleting… {} of {} items done", job.done, items.len()),
+            };
+            let mut cancel = themed_button(
+                theme,
+                &mut self.cancel_delete_job_button,
+                Text::new("Cancel"),
+            )
+            .padding(5);
+            if !job.cancel.load(Ordering::Relaxed) {
+                cancel = cancel.on_press(Message::CancelDeleteJob);
+            }
+            column = column.push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Align::Center)
+                    .push(Text::new(progress))
+                    .push(cancel),
+            );
+        }
+
+        // Progress of a paste running in the background
+        if let Some(job) = &self.paste_job {
+            let progress = match &job.steps {
+                None => format!("Preparing to {}…", job.action.to_lowercase()),
+                Some(steps) => {
+                    format!("{}… {} of {} items done", job.action, job.done, steps.len())
+                }
+            };
+            let mut cancel = themed_button(
+                theme,
+                &mut self.cancel_paste_job_button,
+                Text::new("Cancel"),
+            )
+            .padding(5);
+            if !job.cancel.load(Ordering::Relaxed) {
+                cancel = cancel.on_press(Message::CancelPasteJob);
+            }
+            column = column.push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Align::Center)
+                    .push(Text::new(progress))
+                    .push(cancel),
+            );
+        }
+
+        // Offer to copy files dropped from other folders
+        if !self.pending_drop.is_empty() {
+            let what = match self.pending_drop.as_slice() {
+                [source] => format!(
+                    "\"{}\"",
+                    source
+                        .file_name()
+                        .map(|name| name.to_string_lossy().into_owned())
+                        .unwrap_or_else(|| display_path(source))
+                ),
+                sources => format!("{} dropped items", sources.len()),
+            };
+            column = column.push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Align::Center)
+                    .push(Text::new(format!("Copy {} into {}?", what, display_path(&tab.path))))
+                    .push(
+                        themed_button(theme, &mut self.confirm_drop_button, Text::new("Copy here"))
+                            .on_press(Message::ConfirmDrop)
+                            .padding(5),
+                    )
+                    .push(
+                        themed_button(theme, &mut self.cancel_drop_button, Text::new("Cancel"))
+                            .on_press(Message::CloseDialogs)
+                            .padding(5),
+                    ),
+            );
+        }
+
+        // Properties panel
+        if let Some((target, rows)) = &self.properties {
+            let mut panel = Column::new().spacing(3).push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Align::Center)
+                    .push(Text::new(format!("Properties of {}", display_path(target))).size(16))
+                    .push(
+                        themed_button(
+                            theme,
+                            &mut self.reveal_button,
+                            Text::new("Show in file manager"),
+                        )
+                        .on_press(Message::RevealInOsManager(target.clone()))
+                        .padding(5),
+                    )
+                    .push(
+                        themed_button(theme, &mut self.close_properties_button, Text::new("Close"))
+                            .on_press(Message::CloseDialogs)
+                            .padding(5),
+                    ),
+            );
+            for (label, value) in rows {
+                panel = panel.push(
+                    Row::new()
+                        .push(Text::new(*label).size(14).width(Length::Units(100)))
+                        .push(Text::new(value.as_str()).size(14)),
+                );
+            }
+            // The user's own commands from the settings that apply to this entry
+            let actions = &self.config.custom_actions;
+            self.custom_action_buttons.resize_with(actions.len(), button::State::new);
+            let mut action_row = Row::new().spacing(10);
+            let offered = actions
+                .iter()
+                .zip(self.custom_action_buttons.iter_mut())
+                .enumerate()
+                .filter(|(_, (action, _))| action.applies_to(target, self.properties_is_dir));
+            for (index, (action, state)) in offered {
+                action_row = action_row.push(
+                    themed_button(theme, state, Text::new(action.label.as_str()))
+                        .on_press(Message::RunCustomAction(index, target.clone()))
+                        .padding(5),
+                );
+            }
+            // Tag buttons, the current tag marked
+            let current = self.config.tags.get(target).copied();
+            let mut tag_row = Row::new()
+                .spacing(5)
+                .align_items(Align::Center)
+                .push(Text::new("Tag").size(14).width(Length::Units(100)));
+            let choices = TagColor::ALL.into_iter().map(Some).chain([None]);
+            for (tag, state) in choices.zip(self.tag_buttons.iter_mut()) {
+                let label = match tag {
+                    Some(tag) => Row::new()
+                        .push(Text::new("● ").color(tag.color()))
+                        .push(Text::new(tag.name()).size(14)),
+                    None => Row::new().push(Text::new("none").size(14)),
+                };
+                let mut button = themed_button(theme, state, label).padding(3);
+                if tag != current {
+                    button = button.on_press(Message::SetTag(target.clone(), tag));
+                }
+                tag_row = tag_row.push(button);
+            }
+            column = column.push(panel.push(tag_row).push(action_row));
+        }
+
+        // New folder name prompt
+        if self.new_folder_open {
+            column = column.push(
+                Row::new()
+                    .spacing(10)
+                    .push(Text::new("New folder:"))
+                    .push(
+                        TextInput::new(
+                            &mut self.new_folder_input,
+                            "Folder name",
+                            &self.new_folder_name,
+                            Message::NewFolderNameChanged,
+                        )
+                        .style(InputStyle(theme))
+                        .on_submit(Message::CreateFolder)
+                        .padding(5),
+                    ),
+            );
+            if let Some(err) = &self.new_folder_error {
+                column = column.push(Text::new(err.as_str()).size(14));
+            }
+        }
+
+        // "Go to" dialog
+        if self.goto_open {
+            column = column.push(
+                Row::new()
+                    .spacing(10)
+                    .push(Text::new("Go to:"))
+                    .push(
+                        TextInput::new(
+                            &mut self.goto_input,
+                            if cfg!(windows) {
+                                "~\\projects, %USERPROFILE%\\Desktop, $APPDATA…"
+                            } else {
+                                "~/projects, $HOME/docs…"
+                            },
+                            &self.goto_value,
+                            Message::GoToChanged,
+                        )
+                        .style(InputStyle(theme))
+                        .on_submit(Message::GoToSubmitted)
+                        .padding(5),
+                    ),
+            );
+            if let Some(err) = &self.goto_error {
+                column = column.push(Text::new(err.as_str()).size(14));
+            }
+        }
+
+        // Sibling folder switcher (if shown)
+        if self.show_siblings {
+            self.sibling_buttons
+                .resize_with(self.siblings.len(), button::State::new);
+
+            column = column.push(Text::new("Sibling folders:").size(14));
+
+            let mut siblings_row = Row::new().spacing(5);
+            for (sibling, btn_state) in self.siblings.iter().zip(self.sibling_buttons.iter_mut()) {
+                let name = sibling
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                let label = if *sibling == tab.path { format!("[{}]", name) } else { name };
+
+                siblings_row = siblings_row.push(
+                    themed_button(theme, btn_state, Text::new(label))
+                        .on_press(Message::NavigateTo(sibling.clone()))
+                        .padding(5),
+                );
+            }
+
+            column = column.push(
+                Scrollable::new(&mut self.siblings_scroll)
+                    .push(siblings_row)
+                    .height(Length::Units(40))
+                    .width(Length::Fill),
+            );
+        }
+
+        // Drive selection (if shown)
+        if self.show_drives {
+            self.drive_buttons
+                .resize_with(self.drives.len(), button::State::new);
+
+            column = column.push(Text::new("Select Drive:").size(14));
+            
+            let mut drives_row = Row::new().spacing(5);
+            let drive_buttons = self.drive_buttons.iter_mut();
+            
+            for (drive, btn_state) in self.drives.iter().zip(drive_buttons) {
+                let drive_name = drive.display().to_string();
+                let is_current = Path::new(&display_path(&tab.path)).starts_with(drive);
+                
+                let button = themed_button(
+                    theme,
+                    btn_state, 
+                    Text::new(if is_current {
+                        format!("[{}]", drive_name)
+                    } else {
+                        drive_name.clone()
+                    })
+                )
+                .on_press(Message::DriveSelected(drive.clone()))
+                .padding(5);
+                
+                drives_row = drives_row.push(button);
+            }
+            
+            column = column.push(                        
+                        Scrollable::new(&mut self.drives_scroll)
+                            .push(drives_row)
+                            .height(Length::Units(40))
+                            .width(Length::Fill)
+                    );
+                    
+                    column = column.push(Space::with_height(Length::Units(10)));
+                }
+
+                // Tab strip, one button per open folder
+                self.tab_buttons.resize_with(tab_names.len(), button::State::new);
+                self.close_tab_buttons.resize_with(tab_names.len(), button::State::new);
+                let mut tab_strip = Row::new().spacing(5).align_items(Align::Center);
+                let tab_states = self.tab_buttons.iter_mut().zip(self.close_tab_buttons.iter_mut());
+                for (index, (name, (select_state, close_state))) in
+                    tab_names.into_iter().zip(tab_states).enumerate()
+                {
+                    let mut select =
+                        themed_button(theme, select_state, Text::new(name).size(14))
+                            .on_press(Message::SelectTab(index))
+                            .padding(5);
+                    if index == self.active_tab {
+                        select = select.style(SelectedRow(theme));
+                    }
+                    tab_strip = tab_strip.push(select).push(
+                        themed_button(theme, close_state, Text::new("✕").size(14))
+                            .on_press(Message::CloseTab(index))
+                            .padding(5),
+                    );
+                }
+                tab_strip = tab_strip.push(
+                    themed_button(theme, &mut self.new_tab_button, Text::new("+").size(14))
+                        .on_press(Message::NewTab)
+                        .padding(5),
+                );
+                column = column.push(tab_strip);
+
+                let (placeholder, mode_label) = match self.filter.mode {
+                    FilterMode::Contains => ("Filter by name", "Contains"),
+                    FilterMode::Glob => ("Filter by pattern, like *.rs", "Glob"),
+                };
+                column = column.push(
+                    Row::new()
+                        .spacing(5)
+                        .align_items(Align::Center)
+                        .push(
+                            TextInput::new(
+                                &mut self.filter_input,
+                                placeholder,
+                                &self.filter.text,
+                                Message::FilterChanged,
+                            )
+                            .style(InputStyle(theme))
+                            .padding(5),
+                        )
+                        .push(
+                            themed_button(
+                                theme,
+                                &mut self.filter_mode_button,
+                                Text::new(mode_label).size(14),
+                            )
+                            .on_press(Message::ToggleFilterMode)
+                            .padding(5),
+                        ),
+                );
+                // An invalid glob filters nothing out until it's fixed
+                if let Some(err) = &self.filter.error {
+                    column = column.push(Text::new(err.as_str()).size(14));
+                }
+
+                // Column headers; the active one shows the sort direction
+                let arrow = if self.sort_ascending { " ▲" } else { " ▼" };
+                let header_label = |key: SortKey, name: &str| {
+                    if self.sort_key == key {
+                        format!("{}{}", name, arrow)
+                    } else {
+                        name.to_string()
+                    }
+                };
+                let name_label = header_label(SortKey::Name, "Name");
+                let size_label = header_label(SortKey::Size, "Size");
+                let modified_label = header_label(SortKey::Modified, "Modified");
+                column = column.push(
+                    Row::new()
+                        .spacing(5)
+                        .push(
+                            themed_button(
+                                theme,
+                                &mut self.sort_name_button,
+                                Text::new(name_label).size(14),
+                            )
+                            .on_press(Message::SortBy(SortKey::Name))
+                            .width(Length::Fill)
+                            .padding(3),
+                        )
+                        .push(
+                            themed_button(
+                                theme,
+                                &mut self.sort_size_button,
+                                Text::new(size_label).size(14),
+                            )
+                            .on_press(Message::SortBy(SortKey::Size))
+                            .width(Length::Units(SIZE_COLUMN_WIDTH))
+                            .padding(3),
+                        )
+                        .push(
+                            themed_button(
+                                theme,
+                                &mut self.sort_modified_button,
+                                Text::new(modified_label).size(14),
+                            )
+                            .on_press(Message::SortBy(SortKey::Modified))
+                            .width(Length::Units(MODIFIED_COLUMN_WIDTH))
+                            .padding(3),
+                        ),
+                );
+
+                // Files list with proper spacing
+                let mut files_column = Column::new().spacing(5);
+                tab.file_buttons
+                    .resize_with(tab.files.len(), button::State::new);
+                tab.delete_buttons
+                    .resize_with(tab.files.len(), button::State::new);
+                tab.rename_buttons
+                    .resize_with(tab.files.len(), button::State::new);
+                tab.copy_path_buttons
+                    .resize_with(tab.files.len(), button::State::new);
+                tab.properties_buttons
+                    .resize_with(tab.files.len(), button::State::new);
+                // Only one row is ever being renamed, so it gets the one input state
+                let mut rename_input = Some(&mut self.rename_input);
+
+                // Rows outside the window are skipped and stood in for by space, so even
+                // folders with thousands of entries only build a screenful of widgets
+                let visible_count = tab
+                    .files
+                    .iter()
+                    .filter(|file| self.filter.matches(file))
+                    .count();
+                let window = render_window(&tab.scroll, visible_count);
+                if window.start > 0 {
+                    files_column = files_column.push(row_spacer(window.start));
+                }
+                let trailing = visible_count - window.end;
+                let mut position = 0;
+
+                let rows = tab
+                    .files
+                    .iter()
+                    .zip(tab.file_buttons.iter_mut())
+                    .zip(tab.delete_buttons.iter_mut())
+                    .zip(tab.rename_buttons.iter_mut())
+                    .zip(tab.copy_path_buttons.iter_mut())
+                    .zip(tab.properties_buttons.iter_mut())
+                    .enumerate();
+                for (
+                    index,
+                    (((((file, btn_state), delete_state), rename_state), copy_state), info_state),
+                ) in rows
+                {
+                    if !self.filter.matches(file) {
+                        continue;
+                    }
+                    position += 1;
+                    if !window.contains(&(position - 1)) {
+                        continue;
+                    }
+
+                    // The entry being renamed is an edit field instead of a button
+                    if self.renaming.as_ref() == Some(file)
+                        && let Some(rename_input) = rename_input.take()
+                    {
+                        files_column = files_column.push(
+                            TextInput::new(
+                                rename_input,
+                                "New name",
+                                &self.rename_value,
+                                Message::RenameChanged,
+                            )
+                            .style(InputStyle(theme))
+                            .on_submit(Message::CommitRename(self.rename_value.clone()))
+                            .padding(5),
+                        );
+                        if let Some(err) = &self.rename_error {
+                            files_column = files_column.push(Text::new(err.as_str()).size(14));
+                        }
+                        continue;
+                    }
+
+                    let is_parent = file == Path::new("..");
+                    let meta = tab.entry_meta.get(file).unwrap_or(&EntryMeta::UNKNOWN);
+                    let display_name = if is_parent {
+                        match self.parent_entry_style {
+                            ParentEntryStyle::UpRow => "⬆ Up".to_string(),
+                            _ => ".. (parent)".to_string(),
+                        }
+                    } else if tab.flatten {
+                        // Flattened entries are shown relative to the folder being viewed
+                        file.strip_prefix(&tab.path)
+                            .unwrap_or(file)
+                            .display()
+                            .to_string()
+                    } else {
+                        // Lossy so names that aren't UTF-8 still show (and stay distinct);
+                        // clicks carry the original `PathBuf`
+                        file.file_name()
+                            .map(|name| name.to_string_lossy().into_owned())
+                            .unwrap_or_else(|| display_path(file))
+                    };
+
+                    // 2. Show files/directories with icons
+                    let mut full_text = Row::new();
+                    if let Some(tag) = self.config.tags.get(file) {
+                        full_text = full_text.push(Text::new("● ").color(tag.color()));
+                    }
+                    if !(is_parent && self.parent_entry_style == ParentEntryStyle::UpRow) {
+                        full_text = full_text.push(icon_widget(file, meta.is_dir, icon_set));
+                    }
+                    if meta.is_symlink {
+                        full_text = full_text.push(marker(icon_set, "🔗", "link"));
+                    }
+                    if meta.locked {
+                        full_text = full_text.push(marker(icon_set, "🔒", "lock"));
+                    }
+                    let mut full_text = full_text.push(Text::new(display_name));
+                    // Folder links and broken links say where they point
+                    if (meta.is_dir || meta.broken_link)
+                        && let Some(target) = &meta.link_target
+                    {
+                        let style = self.config.link_target_style;
+                        let resolved = meta.resolved_target.as_deref();
+                        let shown = link_target_text(target, resolved, style);
+                        // "(unresolved)" already says as much in the absolute style
+                        let broken = meta.broken_link && style == LinkTargetStyle::Raw;
+                        let broken = if broken { " (broken)" } else { "" };
+                        full_text = full_text.push(
+                            Text::new(format!("  → {}{}", shown, broken))
+                                .size(14)
+                                .color(theme.dimmed()),
+                        );
+                    }
+                    if self.show_folder_counts && !is_parent && meta.is_dir {
+                        let count = match self.folder_count_cache.get(file) {
+                            Some(Some(count)) => format!("  ({})", count),
+                            Some(None) => "  (—)".to_string(),  // Unreadable
+                            None => "  (…)".to_string(),  // Still counting
+                        };
+                        full_text = full_text.push(Text::new(count));
+                    }
+                    if self.show_dir_sizes && !is_parent && meta.is_dir {
+                        full_text = full_text.push(match self.dir_size_cache.get(file) {
+                            Some(cached) if cached.stale => {
+                                Text::new(format!("  {}", format_size(cached.size)))
+                                    .color(theme.dimmed())
+                            }
+                            Some(cached) => Text::new(format!("  {}", format_size(cached.size))),
+                            None => Text::new("  …"),
+                        });
+                    }
+
+                    let mut full_text = Row::new()
+                        .align_items(Align::Center)
+                        .push(full_text.width(Length::Fill));
+                    if !is_parent {
+                        // Folders leave the size column blank; unreadable entries get a dash
+                        let size = match (meta.is_dir, meta.size) {
+                            (true, _) => String::new(),
+                            (false, Some(size)) => format_size(size),
+                            (false, None) => "—".to_string(),
+                        };
+                        let modified = meta.modified.map_or_else(|| "—".to_string(), format_time);
+                        full_text = full_text
+                            .push(Text::new(size).size(14).width(Length::Units(SIZE_COLUMN_WIDTH)))
+                            .push(
+                                Text::new(modified)
+                                    .size(14)
+                                    .width(Length::Units(MODIFIED_COLUMN_WIDTH)),
+                            );
+                    }
+
+                    // 3. Navigate into directories by clicking
+                    let mut button = themed_button(theme, btn_state, full_text)
+                        .on_press(Message::FileClicked(file.clone()))
+                        .width(Length::Fill)
+                        .padding(5);
+                    // `..` can't join the selection, but the keyboard cursor can rest on it
+                    if tab.selected.contains(file)
+                        || (is_parent && tab.selected_index == Some(index))
+                    {
+                        button = button.style(SelectedRow(theme));
+                    }
+
+                    let mut row = Row::new().spacing(5).align_items(Align::Center).push(button);
+                    if !is_parent {
+                        row = row.push(
+                            themed_button(theme, info_state, Text::new("Info").size(14))
+                                .on_press(Message::ShowProperties(file.clone()))
+                                .padding(5),
+                        );
+                        row = row.push(
+                            themed_button(theme, copy_state, Text::new("Copy path").size(14))
+                                .on_press(Message::CopyPath(file.clone()))
+                                .padding(5),
+                        );
+                        row = row.push(
+                            themed_button(theme, rename_state, Text::new("Rename").size(14))
+                                .on_press(Message::StartRename(file.clone()))
+                                .padding(5),
+                        );
+                        row = row.push(
+                            themed_button(theme, delete_state, Text::new("Delete").size(14))
+                                .on_press(Message::Delete(file.clone()))
+                                .padding(5),
+                        );
+                    }
+                    files_column = files_column.push(row);
+                }
+                if trailing > 0 {
+                    files_column = files_column.push(row_spacer(trailing));
+                }
+                if tab.access_denied {
+                    let denied = "🔒 Access denied: you don't have permission to open this folder";
+                    files_column = files_column.push(Text::new(denied).color(theme.dimmed()));
+                }
+
+                column = column.push(
+                    Scrollable::new(&mut tab.scroll)
+                        .push(files_column)
+                        .height(Length::Fill)
+                );
+
+                // Status bar
+                let mut status = if tab.loading {
+                    "Loading…".to_string()
+                } else {
+                    format!(
+                        "{} folders, {} files — {}",
+                        tab.summary.folders,
+                        tab.summary.files,
+                        format_size(tab.summary.bytes)
+                    )
+                };
+                if let Some(breakdown) = &breakdown {
+                    status = format!(
+                        "{}  ·  {} selected, {}",
+                        status,
+                        breakdown.count,
+                        format_size(breakdown.bytes)
+                    );
+                    if let Some((largest, size)) = &breakdown.largest {
+                        let name = largest.file_name().unwrap_or(largest.as_os_str());
+                        status = format!(
+                            "{}, largest {} ({})",
+                            status,
+                            name.to_string_lossy(),
+                            format_size(*size)
+                        );
+                    }
+                    if breakdown.unmeasured > 0 {
+                        let unmeasured = breakdown.unmeasured;
+                        status = format!("{}, measuring {} folders…", status, unmeasured);
+                    }
+                }
+                if let Some(notice) = &self.notice {
+                    status = format!("{}  ·  {}", status, notice);
+                }
+                let mut status_row = Row::new()
+                    .spacing(10)
+                    .align_items(Align::Center)
+                    .push(Text::new(status).size(14));
+                if breakdown.is_some() {
+                    status_row = status_row.push(
+                        themed_button(
+                            theme,
+                            &mut self.copy_breakdown_button,
+                            Text::new("Copy breakdown").size(14),
+                        )
+                        .on_press(Message::CopySelectionBreakdown)
+                        .padding(3),
+                    );
+                }
+                column = column.push(status_row);
+
+                // Bookmarks sidebar
+                self.bookmark_buttons
+                    .resize_with(self.config.bookmarks.len(), button::State::new);
+                self.remove_bookmark_buttons
+                    .resize_with(self.config.bookmarks.len(), button::State::new);
+                let mut sidebar = Column::new()
+                    .spacing(5)
+                    .padding(10)
+                    .width(Length::Units(200))
+                    .push(Text::new("Bookmarks").size(16));
+                if self.config.bookmarks.is_empty() {
+                    sidebar = sidebar.push(Text::new("No bookmarks yet").size(14));
+                }
+                for (((bookmark, exists), btn_state), remove_state) in self
+                    .config
+                    .bookmarks
+                    .iter()
+                    .zip(self.bookmark_exists.iter())
+                    .zip(self.bookmark_buttons.iter_mut())
+                    .zip(self.remove_bookmark_buttons.iter_mut())
+                {
+                    let name = bookmark
+                        .file_name()
+                        .map(|name| name.to_string_lossy().into_owned())
+                        .unwrap_or_else(|| display_path(bookmark));
+                    // A missing folder stays listed (so it can be removed) but can't be opened
+                    let mut open = themed_button(
+                        theme,
+                        btn_state,
+                        Text::new(if *exists { name } else { format!("{} (missing)", name) })
+                            .size(14),
+                    )
+                    .width(Length::Fill)
+                    .padding(5);
+                    if *exists {
+                        open = open.on_press(Message::NavigateTo(bookmark.clone()));
+                    }
+                    sidebar = sidebar.push(
+                        Row::new()
+                            .spacing(5)
+                            .align_items(Align::Center)
+                            .push(open)
+                            .push(
+                                themed_button(theme, remove_state, Text::new("✕").size(14))
+                                    .on_press(Message::RemoveBookmark(bookmark.clone()))
+                                    .padding(5),
+                            ),
+                    );
+                }
+
+                // Preview pane, beside the list while an image is selected
+                let mut layout = Row::new().push(sidebar).push(column);
+                if let Some(preview) = &self.preview {
+                    let name = preview
+                        .path
+                        .file_name()
+                        .map(|name| name.to_string_lossy().into_owned())
+                        .unwrap_or_default();
+                    let details = match (preview.loaded, preview.dimensions) {
+                        (false, _) => "Loading…".to_string(),
+                        (true, Some((width, height))) => format!("{} × {} pixels", width, height),
+                        (true, None) => "Not a readable image".to_string(),
+                    };
+                    // The pixels themselves need iced's `image` feature, which this build
+                    // can't enable yet; the pane shows what the header tells us meanwhile
+                    layout = layout.push(
+                        Column::new()
+                            .spacing(5)
+                            .padding(10)
+                            .width(Length::Units(220))
+                            .push(Text::new("Preview").size(16))
+                            .push(Text::new(name).size(14))
+                            .push(Text::new(details).size(14).color(theme.dimmed())),
+                    );
+                }
+
+                Container::new(layout)
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .style(Root(theme))
+                    .into()
+            }
+        }
+
+/// The rows of the filtered list worth building widgets for, given how far the
+/// file list is scrolled by `scroll`.
+fn render_window(scroll: &scrollable::State, visible_count: usize) -> std::ops::Range<usize> {
+    // Against unbounded content, `offset` hands back the raw scroll position
+    let viewport = iced::Rectangle { x: 0.0, y: 0.0, width: 0.0, height: 0.0 };
+    let content = iced::Rectangle { height: f32::MAX, ..viewport };
+    let top_row = (scroll.offset(viewport, content) as f32 / ROW_STRIDE) as usize;
+    // The stored offset can run past the end after the list shrinks
+    let first = top_row
+        .saturating_sub(RENDER_OVERSCAN)
+        .min(visible_count.saturating_sub(RENDER_WINDOW));
+    first..(first + RENDER_WINDOW).min(visible_count)
+}
+
+/// Blank space as tall as `rows` file rows, less the column spacing that follows it.
+/// Built from several pieces because a single `Space` can't be taller than `u16::MAX`.
+fn row_spacer<'a>(rows: usize) -> Column<'a, Message> {
+    let mut height = (rows as f32 * ROW_STRIDE - 5.0) as usize;
+    let mut spacer = Column::new();
+    while height > 0 {
+        let piece = height.min(u16::MAX as usize);
+        spacer = spacer.push(Space::with_height(Length::Units(piece as u16)));
+        height -= piece;
+    }
+    spacer
+}
+
+/// Formats a byte count for display, e.g. `532 B`, `4.2 KB`, `1.3 GB`.
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// Container style for the whole window: the theme's background and text colour.
+struct Root(Theme);
+
+impl container::StyleSheet for Root {
+    fn style(&self) -> container::Style {
+        container::Style {
+            text_color: Some(self.0.text()),
+            background: Some(Background::Color(self.0.background())),
+            ..container::Style::default()
+        }
+    }
+}
+
+/// Container style for the error banner.
+struct ErrorBanner(Theme);
+
+impl container::StyleSheet for ErrorBanner {
+    fn style(&self) -> container::Style {
+        let background = match self.0 {
+            Theme::Light => Color::from_rgb(0.75, 0.15, 0.15),
+            Theme::Dark => Color::from_rgb(0.55, 0.12, 0.12),
+        };
+        container::Style {
+            text_color: Some(Color::WHITE),
+            background: Some(Background::Color(background)),
+            border_radius: 4.0,
+            ..container::Style::default()
+        }
+    }
+}
+
+/// Button style for the highlighted row in the file list.
+struct SelectedRow(Theme);
+
+impl button::StyleSheet for SelectedRow {
+    fn active(&self) -> button::Style {
+        let (background, text_color) = match self.0 {
+            Theme::Light => (Color::from_rgb(0.55, 0.7, 0.95), Color::BLACK),
+            Theme::Dark => (Color::from_rgb(0.2, 0.35, 0.6), Color::WHITE),
+        };
+        button::Style {
+            background: Some(Background::Color(background)),
+            border_radius: 2.0,
+            text_color,
+            ..button::Style::default()
+        }
+    }
+}
+
+/// Button style for every other button.
+struct PlainButton(Theme);
+
+impl button::StyleSheet for PlainButton {
+    fn active(&self) -> button::Style {
+        let (background, border_color) = match self.0 {
+            // iced's own default look
+            Theme::Light => (Color::from_rgb(0.87, 0.87, 0.87), Color::from_rgb(0.7, 0.7, 0.7)),
+            Theme::Dark => (Color::from_rgb(0.25, 0.25, 0.28), Color::from_rgb(0.4, 0.4, 0.45)),
+        };
+        button::Style {
+            background: Some(Background::Color(background)),
+            border_radius: 2.0,
+            border_width: 1.0,
+            border_color,
+            text_color: self.0.text(),
+            ..button::Style::default()
+        }
+    }
+}
+
+/// Text input style.
+struct InputStyle(Theme);
+
+impl text_input::StyleSheet for InputStyle {
+    fn active(&self) -> text_input::Style {
+        let (background, border_color) = match self.0 {
+            Theme::Light => (Color::WHITE, Color::from_rgb(0.7, 0.7, 0.7)),
+            Theme::Dark => (Color::from_rgb(0.18, 0.18, 0.2), Color::from_rgb(0.4, 0.4, 0.45)),
+        };
+        text_input::Style {
+            background: Background::Color(background),
+            border_radius: 5.0,
+            border_width: 1.0,
+            border_color,
+        }
+    }
+
+    fn focused(&self) -> text_input::Style {
+        let border_color = match self.0 {
+            Theme::Light => Color::from_rgb(0.5, 0.5, 0.5),
+            Theme::Dark => Color::from_rgb(0.6, 0.6, 0.7),
+        };
+        text_input::Style { border_color, ..self.active() }
+    }
+
+    fn placeholder_color(&self) -> Color {
+        match self.0 {
+            Theme::Light => Color::from_rgb(0.7, 0.7, 0.7),
+            Theme::Dark => Color::from_rgb(0.45, 0.45, 0.45),
+        }
+    }
+
+    fn value_color(&self) -> Color {
+        match self.0 {
+            Theme::Light => Color::from_rgb(0.3, 0.3, 0.3),
+            Theme::Dark => Color::from_rgb(0.9, 0.9, 0.9),
+        }
+    }
+
+    fn selection_color(&self) -> Color {
+        match self.0 {
+            Theme::Light => Color::from_rgb(0.8, 0.8, 1.0),
+            Theme::Dark => Color::from_rgb(0.3, 0.4, 0.65),
+        }
+    }
+}
+
+/// A button in the plain style of `theme`.
+fn themed_button<'a>(
+    theme: Theme,
+    state: &'a mut button::State,
+    content: impl Into<Element<'a, Message>>,
+) -> Button<'a, Message> {
+    Button::new(state, content).style(PlainButton(theme))
+}
+
+/// Maps key presses to the app's keyboard shortcuts.
+fn shortcut_for(event: Event, status: event::Status) -> Option<Message> {
+    match event {
+        Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) => {
+            Some(Message::ModifiersChanged(modifiers))
+        }
+        Event::Keyboard(keyboard::Event::KeyPressed { key_code, modifiers }) => {
+            match key_code {
+                keyboard::KeyCode::G if modifiers.is_command_pressed() => Some(Message::ShowGoTo),
+                keyboard::KeyCode::R if modifiers.is_command_pressed() && modifiers.shift => {
+                    Some(Message::ReloadConfig)
+                }
+                keyboard::KeyCode::Escape => Some(Message::CloseDialogs),
+                // Leave these to a focused text input, which captures them
+                _ if status == event::Status::Captured => None,
+                keyboard::KeyCode::T if modifiers.is_command_pressed() && modifiers.shift => {
+                    Some(Message::NewTab)
+                }
+                keyboard::KeyCode::T if modifiers.is_command_pressed() => {
+                    Some(Message::OpenSelectedInNewTab)
+                }
+                keyboard::KeyCode::Up => Some(Message::SelectPrevious),
+                keyboard::KeyCode::Down => Some(Message::SelectNext),
+                keyboard::KeyCode::Space if modifiers.is_command_pressed() => {
+                    Some(Message::ToggleCursorSelected)
+                }
+                keyboard::KeyCode::Enter => Some(Message::OpenSelected),
+                keyboard::KeyCode::Backspace => Some(Message::GoUp),
+                keyboard::KeyCode::C if modifiers.is_command_pressed() => {
+                    Some(Message::CopySelected)
+                }
+                keyboard::KeyCode::X if modifiers.is_command_pressed() => {
+                    Some(Message::CutSelected)
+                }
+                keyboard::KeyCode::V if modifiers.is_command_pressed() => Some(Message::Paste),
+                _ => None,
+            }
+        }
+        // Typing into a focused text input doesn't count; it captures the characters
+        Event::Keyboard(keyboard::Event::CharacterReceived(c))
+            if status == event::Status::Ignored && !c.is_control() =>
+        {
+            Some(Message::TypeAhead(c))
+        }
+        Event::Window(window::Event::FileDropped(path)) => Some(Message::FileDropped(path)),
+        Event::Window(window::Event::CloseRequested) => Some(Message::CloseRequested),
+        // Scrolling doesn't otherwise produce a message, and without one the view (and
+        // so the window of rows that gets built) isn't rebuilt
+        Event::Mouse(
+            mouse::Event::WheelScrolled { .. }
+            | mouse::Event::CursorMoved { .. }
+            | mouse::Event::ButtonPressed(_),
+        ) if status == event::Status::Captured => Some(Message::ListScrolled),
+        _ => None,
+    }
+}
+
+/// The label/value rows of the properties panel for `path`. Anything the platform or
+/// filesystem can't tell us is shown as "n/a".
+fn read_properties(path: &Path, link_style: LinkTargetStyle) -> Vec<(&'static str, String)> {
+    let na = || "n/a".to_string();
+    let link_meta = fs::symlink_metadata(long_path(path)).ok();
+    let is_symlink = link_meta.as_ref().map(|meta| meta.file_type().is_symlink());
+    // Describe what a link points at; a broken link only has its own metadata
+    let meta = fs::metadata(long_path(path)).ok().or(link_meta);
+    let time = |read: fn(&fs::Metadata) -> io::Result<SystemTime>| {
+        meta.as_ref().and_then(|meta| read(meta).ok()).map_or_else(na, format_time)
+    };
+
+    let mut rows = vec![
+        ("Path", display_path(path)),
+        (
+            "Size",
+            // A folder's own length says nothing about its contents
+            meta.as_ref().filter(|meta| !meta.is_dir()).map_or_else(na, |meta| {
+                format!("{} bytes ({})", meta.len(), format_size(meta.len()))
+            }),
+        ),
+        ("Created", time(fs::Metadata::created)),
+        ("Modified", time(fs::Metadata::modified)),
+        ("Accessed", time(fs::Metadata::accessed)),
+        ("Symlink", is_symlink.map_or_else(na, |link| if link { "yes" } else { "no" }.to_string())),
+    ];
+    if is_symlink == Some(true) {
+        rows.push(("Target", read_link_target(path, link_style)));
+    }
+    rows.push((
+        "Read-only",
+        meta.as_ref().map_or_else(na, |meta| {
+            if meta.permissions().readonly() { "yes" } else { "no" }.to_string()
+        }),
+    ));
+    rows
+}
+
+/// Where the symlink at `path` points, in `style`, or "n/a" if the link can't be read.
+fn read_link_target(path: &Path, style: LinkTargetStyle) -> String {
+    match fs::read_link(long_path(path)) {
+        Ok(raw) => link_target_text(&raw, fs::canonicalize(long_path(path)).ok().as_deref(), style),
+        Err(_) => "n/a".to_string(),
+    }
+}
+
+/// A link's target for display: `raw` as stored, or `resolved` when `style` asks for
+/// the absolute path. A link that can't be resolved shows `raw`, marked as such.
+fn link_target_text(raw: &Path, resolved: Option<&Path>, style: LinkTargetStyle) -> String {
+    match (style, resolved) {
+        (LinkTargetStyle::Raw, _) => display_path(raw),
+        (LinkTargetStyle::Absolute, Some(resolved)) => display_path(resolved),
+        (LinkTargetStyle::Absolute, None) => format!("{} (unresolved)", display_path(raw)),
+    }
+}
+
+/// Formats a timestamp as `YYYY-MM-DD HH:MM` in local time (UTC where the local
+/// offset can't be found).
+fn format_time(time: SystemTime) -> String {
+    let secs = match time.duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(since) => since.as_secs() as i64,
+        Err(before) => -(before.duration().as_secs() as i64),
+    };
+    let local = secs + utc_offset_secs(secs);
+    let (days, of_day) = (local.div_euclid(86_400), local.rem_euclid(86_400));
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!("{:04}-{:02}-{:02} {:02}:{:02}", year, month, day, of_day / 3600, of_day % 3600 / 60)
+}
+
+/// Offset of local time from UTC at `secs` past the epoch.
+#[cfg(unix)]
+fn utc_offset_secs(secs: i64) -> i64 {
+    let time = secs as libc::time_t;
+    // SAFETY: localtime_r only writes to the `tm` we hand it
+    unsafe {
+        let mut tm: libc::tm = std::mem::zeroed();
+        if libc::localtime_r(&time, &mut tm).is_null() {
+            0
+        } else {
+            tm.tm_gmtoff as i64
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn utc_offset_secs(_secs: i64) -> i64 {
+    0
+}
+
+/// Builds the icon shown in front of an entry's name in the file list.
+///
+/// An embedded SVG set would plug in here once the `svg` feature of iced can be
+/// enabled for this build.
+fn icon_widget(path: &Path, is_dir: bool, icon_set: IconSet) -> Element<'static, Message> {
+    let (emoji, label) = file_icon(path, is_dir);
+    marker(icon_set, emoji, label)
 }
 
-impl FileExplorer {
-    fn list_files(&mut self) -> Command<Message> {
-        let files = self.list_files_in_directory(&self.path);
-        self.files = files;
-        Command::none()
+/// One of the row's icons, as an emoji or a short label depending on `icon_set`.
+fn marker(
+    icon_set: IconSet,
+    emoji: &'static str,
+    label: &'static str,
+) -> Element<'static, Message> {
+    match icon_set {
+        IconSet::Emoji => Text::new(format!("{} ", emoji)).into(),
+        // Labels differ in length, so give them a column of their own
+        IconSet::Text => Text::new(label).size(14).width(Length::Units(36)).into(),
     }
+}
 
-    fn list_files_in_directory(&self, path: &Path) -> Vec<PathBuf> {
-        let mut files = Vec::new();
-        
-        if path.parent().is_some() {
-            files.push(PathBuf::from(".."));
-        }
-        
-        if let Ok(entries) = fs::read_dir(path) {
-            for entry in entries.flatten() {
-                files.push(entry.path());
+const IMAGE_EXTENSIONS: [&str; 10] =
+    ["png", "jpg", "jpeg", "gif", "bmp", "webp", "svg", "ico", "tif", "tiff"];
+
+/// Whether `path` has one of the image extensions (case-insensitive).
+fn is_image(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .is_some_and(|ext| IMAGE_EXTENSIONS.contains(&ext.as_str()))
+}
+
+/// Reads an image's width and height from its header. PNG, GIF, BMP and JPEG are
+/// understood; anything else (or a damaged file) gives `None`.
+fn image_dimensions(path: &Path) -> Option<(u32, u32)> {
+    use std::io::{Read, Seek, SeekFrom};
+
+    let mut file = fs::File::open(long_path(path)).ok()?;
+    let mut header = [0u8; 26];
+    let read = file.read(&mut header).ok()?;
+    let header = &header[..read];
+    let be32 = |at: usize| u32::from_be_bytes(header[at..at + 4].try_into().unwrap());
+    let le16 = |at: usize| u32::from(u16::from_le_bytes([header[at], header[at + 1]]));
+    let le32 = |at: usize| i32::from_le_bytes(header[at..at + 4].try_into().unwrap());
+
+    if header.starts_with(b"\x89PNG\r\n\x1a\n") && read >= 24 {
+        Some((be32(16), be32(20)))
+    } else if header.starts_with(b"GIF8") && read >= 10 {
+        Some((le16(6), le16(8)))
+    } else if header.starts_with(b"BM") && read >= 26 {
+        // Height is negative for top-down bitmaps
+        Some((le32(18).unsigned_abs(), le32(22).unsigned_abs()))
+    } else if header.starts_with(&[0xFF, 0xD8]) {
+        // Walk the JPEG segments up to the start-of-frame, which holds the size
+        file.seek(SeekFrom::Start(2)).ok()?;
+        loop {
+            let mut marker = [0u8; 4];
+            file.read_exact(&mut marker).ok()?;
+            if marker[0] != 0xFF {
+                return None;
             }
-        }
-        
-        files.sort_by(|a, b| {
-            let a_is_dir = a.is_dir();
-            let b_is_dir = b.is_dir();
-            if a_is_dir && !b_is_dir {
-                std::cmp::Ordering::Less
-            } else if !a_is_dir && b_is_dir {
-                std::cmp::Ordering::Greater
-            } else {
-                a.file_name().cmp(&b.file_name())
+            let length = u16::from_be_bytes([marker[2], marker[3]]);
+            let is_frame = matches!(marker[1], 0xC0..=0xCF)
+                && !matches!(marker[1], 0xC4 | 0xC8 | 0xCC);
+            if is_frame {
+                let mut frame = [0u8; 5];
+                file.read_exact(&mut frame).ok()?;
+                let height = u16::from_be_bytes([frame[1], frame[2]]);
+                let width = u16::from_be_bytes([frame[3], frame[4]]);
+                return Some((u32::from(width), u32::from(height)));
             }
-        });
-        
-        files
+            file.seek(SeekFrom::Current(i64::from(length) - 2)).ok()?;
+        }
+    } else {
+        None
     }
-    
-    fn get_available_drives() -> Vec<PathBuf> {
-        if cfg!(windows) {
-            (b'A'..=b'Z')
-                .map(|c| format!("{}:", c as char))
-                .map(PathBuf::from)
-                .filter(|drive| drive.exists())
-                .collect()
-        } else {
-            vec![PathBuf::from("/")]
+}
+
+/// Picks an entry's icon, as an emoji and a short label: the folder icon for folders,
+/// otherwise one from the extension (case-insensitive), with the generic file icon for
+/// anything unrecognised. `is_dir` is true for links to folders too.
+fn file_icon(path: &Path, is_dir: bool) -> (&'static str, &'static str) {
+    if is_dir {
+        return ("📁", "dir");
+    }
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        ext if IMAGE_EXTENSIONS.contains(&ext) => ("🖼", "img"),
+        "zip" | "tar" | "gz" | "tgz" | "bz2" | "xz" | "zst" | "7z" | "rar" => ("🗜", "arc"),
+        "mp3" | "wav" | "flac" | "ogg" | "m4a" | "aac" | "opus" => ("🎵", "aud"),
+        "mp4" | "mkv" | "mov" | "avi" | "webm" => ("🎞", "vid"),
+        "rs" | "c" | "h" | "cpp" | "hpp" | "py" | "js" | "ts" | "go" | "java" | "sh" | "toml"
+        | "json" | "yaml" | "yml" | "html" | "css" => ("📜", "src"),
+        "txt" | "md" | "log" | "csv" | "rtf" => ("📝", "txt"),
+        "pdf" => ("📕", "pdf"),
+        "exe" | "msi" | "bat" | "cmd" | "com" | "app" | "appimage" | "deb" | "rpm" => {
+            ("⚙", "exe")
         }
+        _ => ("📄", "file"),
     }
+}
 
-    fn open_file(&self, file_path: &Path) {
-        if cfg!(windows) {
-            if let Some(valid_path) = file_path.to_str() {
-                if let Err(err) = ProcessCommand::new("cmd")
-                    .args(&["/C", "start", valid_path])
-                    .spawn()
-                {
-                    eprintln!("Failed to open file (Windows): {}", err);
-                }
+/// Splits `path` into breadcrumb labels, each paired with the folder it leads to. The
+/// root is one crumb: `/` on Unix, the drive (`C:\`) or share on Windows.
+fn breadcrumbs(path: &Path) -> Vec<(String, PathBuf)> {
+    let mut crumbs: Vec<(String, PathBuf)> = Vec::new();
+    let mut prefix = PathBuf::new();
+    for component in path.components() {
+        prefix.push(component);
+        match component {
+            Component::Prefix(drive) => {
+                // Drop the `\\?\` that long_path may have added
+                let label = display_path(Path::new(drive.as_os_str()));
+                crumbs.push((label, prefix.clone()));
             }
-        } else {
-            if let Err(err) = ProcessCommand::new("xdg-open")
-                .arg(file_path)  // Use to_string_lossy for safe conversion
-                .spawn()
-            {
-                eprintln!("Failed to open file (Linux/macOS): {}", err);
+            Component::RootDir => match crumbs.last_mut() {
+                // `C:` followed by `\` is the drive root
+                Some((label, target)) => {
+                    label.push(std::path::MAIN_SEPARATOR);
+                    *target = prefix.clone();
+                }
+                None => crumbs.push((std::path::MAIN_SEPARATOR.to_string(), prefix.clone())),
+            },
+            Component::Normal(name) => {
+                crumbs.push((name.to_string_lossy().into_owned(), prefix.clone()));
             }
+            Component::CurDir | Component::ParentDir => {}
         }
     }
+    crumbs
 }
 
-impl Application for FileExplorer {
-    type Message = Message;
-    type Executor = iced::executor::Default;
-    type Flags = ();
+/// Lets a second `fex <dir>` hand its directory to the instance that is already
+/// running, over a Unix socket in the user's runtime directory.
+#[cfg(unix)]
+mod ipc {
+    use super::Message;
+    use iced::futures::{channel::mpsc, stream::BoxStream, StreamExt};
+    use std::{
+        ffi::OsString,
+        hash::{Hash, Hasher},
+        io::{self, BufRead, BufReader, Write},
+        os::unix::{
+            ffi::{OsStrExt, OsStringExt},
+            net::{UnixListener, UnixStream},
+        },
+        path::{Path, PathBuf},
+        sync::Arc,
+        thread,
+    };
 
-    fn new(_flags: ()) -> (FileExplorer, Command<Message>) {
-        let drives = FileExplorer::get_available_drives();
-        let drive_buttons = drives.iter().map(|_| button::State::new()).collect();
-        
-        let mut explorer = FileExplorer {
-            path: env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
-            drives,
-            drive_buttons,
-            show_drives: false,
-            last_click_time: None,  // Initialize with no click
-            ..FileExplorer::default()
-        };
-        explorer.list_files();
-        (explorer, Command::none())
+    pub enum Instance {
+        /// We are the only instance and now own the socket.
+        Primary(UnixListener),
+        /// Another instance took our path; nothing left to do.
+        Forwarded,
     }
 
-    fn title(&self) -> String {
-        String::from("File Explorer")
+    fn socket_path() -> PathBuf {
+        dirs::runtime_dir()
+            .unwrap_or_else(std::env::temp_dir)
+            .join("fex.sock")
     }
 
-    fn update(
-        &mut self,
-        message: Self::Message,
-        _clipboard: &mut iced::Clipboard,
-    ) -> Command<Message> {
-        match message {
-            Message::FileClicked(path) => {
-                let now = Instant::now();
-                
-                // If we had a previous click and it's within 500ms, consider it a double-click
-                if let Some(last_click) = self.last_click_time {
-                    if now.duration_since(last_click) < Duration::from_millis(500) {
-                        // Double-click detected, open the file
-                        if path.is_file() {
-                            self.open_file(&path);
+    /// Binds the instance socket, or forwards `path` to whichever instance holds it.
+    pub fn claim(path: Option<&Path>) -> io::Result<Instance> {
+        let socket = socket_path();
+        match UnixListener::bind(&socket) {
+            Ok(listener) => Ok(Instance::Primary(listener)),
+            Err(err) if err.kind() == io::ErrorKind::AddrInUse => {
+                match (UnixStream::connect(&socket), path) {
+                    (Ok(mut stream), Some(path)) => {
+                        stream.write_all(path.as_os_str().as_bytes())?;
+                        stream.write_all(b"\n")?;
+                        Ok(Instance::Forwarded)
+                    }
+                    // A live instance but nothing to hand over: run alongside it
+                    (Ok(_), None) => Err(err),
+                    // Left behind by an instance that didn't shut down cleanly
+                    (Err(_), _) => {
+                        std::fs::remove_file(&socket)?;
+                        UnixListener::bind(&socket).map(Instance::Primary)
+                    }
+                }
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Subscription turning each path received on the socket into `Message::NavigateTo`.
+    pub struct Listen(pub Arc<UnixListener>);
+
+    impl<H: Hasher, I> iced_native::subscription::Recipe<H, I> for Listen {
+        type Output = Message;
+
+        fn hash(&self, state: &mut H) {
+            std::any::TypeId::of::<Self>().hash(state);
+        }
+
+        fn stream(self: Box<Self>, _input: BoxStream<'static, I>) -> BoxStream<'static, Message> {
+            let (sender, receiver) = mpsc::unbounded();
+            let listener = self.0;
+            thread::spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    for line in BufReader::new(stream).split(b'\n').flatten() {
+                        let path = PathBuf::from(OsString::from_vec(line));
+                        if sender.unbounded_send(Message::NavigateTo(path)).is_err() {
+                            return;
                         }
                     }
                 }
+            });
+            receiver.boxed()
+        }
+    }
+}
 
-                // Update the last click time
-                self.last_click_time = Some(now);
+/// Settings kept between runs in `<config dir>/fex/settings`, one `key=value` per line.
+mod config {
+    use super::{launcher::CustomAction, ClickMode, IconSet, LinkTargetStyle, TagColor, Theme};
+    use std::{
+        collections::HashMap,
+        fs,
+        io::{self, Write},
+        path::{Path, PathBuf},
+    };
 
-                // Handle file/directory navigation
-                let target_path = if path == PathBuf::from("..") {
-                    self.path.parent().map_or(self.path.clone(), |p| p.to_path_buf())
-                } else if path.is_relative() {
-                    self.path.join(&path)
-                } else {
-                    path
+    #[derive(Debug, Clone)]
+    pub struct Config {
+        pub last_dir: Option<PathBuf>,  // Folder open when we last navigated
+        pub bookmarks: Vec<PathBuf>,  // In the order they were added
+        pub theme: Theme,
+        pub click_mode: ClickMode,
+        // Longest gap between the two clicks of a double-click; kept in single-click
+        // mode too, so switching back doesn't lose a custom value
+        pub double_click_ms: u64,
+        pub icon_set: IconSet,
+        pub link_target_style: LinkTargetStyle,
+        pub recent_destinations: Vec<PathBuf>,  // Folders pasted into, most recent first
+        // Ask before leaving a folder, a tab or the app throws away a dialog's input
+        pub confirm_discard: bool,
+        pub custom_actions: Vec<CustomAction>,  // In the order they're listed
+        pub tags: HashMap<PathBuf, TagColor>,
+    }
+
+    impl Default for Config {
+        fn default() -> Self {
+            Config {
+                last_dir: None,
+                bookmarks: Vec::new(),
+                theme: Theme::default(),
+                click_mode: ClickMode::default(),
+                double_click_ms: ClickMode::DEFAULT_THRESHOLD_MS,
+                icon_set: IconSet::default(),
+                link_target_style: LinkTargetStyle::default(),
+                recent_destinations: Vec::new(),
+                confirm_discard: true,
+                custom_actions: Vec::new(),
+                tags: HashMap::new(),
+            }
+        }
+    }
+
+    /// `dir` as it's written to the file; a path that isn't UTF-8 or spans lines can't
+    /// be stored in this format.
+    fn storable(dir: &Path) -> Option<&str> {
+        dir.to_str().filter(|dir| !dir.contains('\n'))
+    }
+
+    fn config_path() -> Option<PathBuf> {
+        // Tests must never read or overwrite the real settings
+        if cfg!(test) {
+            return None;
+        }
+        dirs::config_dir().map(|dir| dir.join("fex").join("settings"))
+    }
+
+    impl Config {
+        /// Reads the saved settings. A missing or unreadable file gives the defaults, and
+        /// lines we don't understand are skipped.
+        pub fn load() -> Config {
+            config_path()
+                .and_then(|path| fs::read_to_string(path).ok())
+                .and_then(|text| Config::parse(&text, false).ok())
+                .unwrap_or_default()
+        }
+
+        /// Re-reads the settings after they were edited by hand. Unlike `load`, anything
+        /// in the file we don't understand is an error naming the line, so a typo isn't
+        /// silently dropped.
+        pub fn reload() -> Result<Config, String> {
+            let path = config_path().ok_or("no configuration folder on this system")?;
+            let text = fs::read_to_string(&path)
+                .map_err(|err| format!("cannot read {}: {}", path.display(), err))?;
+            Config::parse(&text, true)
+        }
+
+        /// Builds the settings from the file's text. When `strict`, the first line that
+        /// doesn't make sense fails the parse; otherwise such lines are skipped.
+        fn parse(text: &str, strict: bool) -> Result<Config, String> {
+            let mut config = Config::default();
+            for (number, line) in text.lines().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let valid = match line.split_once('=') {
+                    None => false,
+                    Some((key, value)) => config.apply(key.trim(), value),
                 };
+                if !valid && strict {
+                    return Err(format!("line {} is not a valid setting: {}", number + 1, line));
+                }
+            }
+            Ok(config)
+        }
 
-                if target_path.is_dir() {
-                    self.path = target_path;
-                    self.show_drives = false;
-                    self.list_files()
-                } else {
-                    println!("File selected: {:?}", target_path);
-                    Command::none()  // No further action if it’s just a click (not a double-click)
+        /// Sets what one `key=value` line says, returning whether it meant anything.
+        fn apply(&mut self, key: &str, value: &str) -> bool {
+            match key {
+                "last_dir" if !value.is_empty() => self.last_dir = Some(value.into()),
+                "bookmark" if !value.is_empty() => self.bookmarks.push(value.into()),
+                "recent_destination" if !value.is_empty() => {
+                    self.recent_destinations.push(value.into());
                 }
+                "theme" => match value.trim() {
+                    "light" => self.theme = Theme::Light,
+                    "dark" => self.theme = Theme::Dark,
+                    _ => return false,
+                },
+                "click_mode" => match value.trim() {
+                    "single" => self.click_mode = ClickMode::SingleClick,
+                    "double" => self.click_mode = ClickMode::DoubleClick,
+                    _ => return false,
+                },
+                "double_click_ms" => match value.trim().parse() {
+                    Ok(ms) => self.double_click_ms = ms,
+                    Err(_) => return false,
+                },
+                "icon_set" => match value.trim() {
+                    "emoji" => self.icon_set = IconSet::Emoji,
+                    "text" => self.icon_set = IconSet::Text,
+                    _ => return false,
+                },
+                // The colour goes first so the path can hold anything
+                "tag" => match value.split_once('|') {
+                    Some((color, path)) if !path.is_empty() => match TagColor::from_name(color) {
+                        Some(tag) => {
+                            self.tags.insert(path.into(), tag);
+                        }
+                        None => return false,
+                    },
+                    _ => return false,
+                },
+                "custom_action" => match CustomAction::parse(value) {
+                    Some(action) => self.custom_actions.push(action),
+                    None => return false,
+                },
+                "confirm_discard" => match value.trim().parse() {
+                    Ok(confirm) => self.confirm_discard = confirm,
+                    Err(_) => return false,
+                },
+                "symlink_target_style" => match value.trim() {
+                    "raw" => self.link_target_style = LinkTargetStyle::Raw,
+                    "absolute" => self.link_target_style = LinkTargetStyle::Absolute,
+                    _ => return false,
+                },
+                _ => return false,
             }
-            Message::Refresh => self.list_files(),
-            Message::GoUp => {
-                if let Some(parent) = self.path.parent() {
-                    self.path = parent.to_path_buf();
-                    self.show_drives = false;
-                    self.list_files()
-                } else {
-                    self.show_drives = true;
-                    Command::none()
+            true
+        }
+
+        /// Writes the settings to a temporary file and renames it over the old one, so an
+        /// interrupted save never leaves a half-written file behind.
+        pub fn save(&self) -> io::Result<()> {
+            let path = config_path()
+                .ok_or_else(|| io::Error::other("no configuration folder on this system"))?;
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+
+            let mut text = String::new();
+            if let Some(dir) = self.last_dir.as_deref().and_then(storable) {
+                text.push_str(&format!("last_dir={}\n", dir));
+            }
+            for dir in self.bookmarks.iter().map(PathBuf::as_path).filter_map(storable) {
+                text.push_str(&format!("bookmark={}\n", dir));
+            }
+            let recent = self.recent_destinations.iter().map(PathBuf::as_path);
+            for dir in recent.filter_map(storable) {
+                text.push_str(&format!("recent_destination={}\n", dir));
+            }
+            let theme = match self.theme {
+                Theme::Light => "light",
+                Theme::Dark => "dark",
+            };
+            text.push_str(&format!("theme={}\n", theme));
+            let click_mode = match self.click_mode {
+                ClickMode::DoubleClick => "double",
+                ClickMode::SingleClick => "single",
+            };
+            text.push_str(&format!("click_mode={}\n", click_mode));
+            text.push_str(&format!("double_click_ms={}\n", self.double_click_ms));
+            let icon_set = match self.icon_set {
+                IconSet::Emoji => "emoji",
+                IconSet::Text => "text",
+            };
+            text.push_str(&format!("icon_set={}\n", icon_set));
+            let link_style = match self.link_target_style {
+                LinkTargetStyle::Raw => "raw",
+                LinkTargetStyle::Absolute => "absolute",
+            };
+            text.push_str(&format!("symlink_target_style={}\n", link_style));
+            text.push_str(&format!("confirm_discard={}\n", self.confirm_discard));
+            // Sorted so saving doesn't reshuffle the file
+            let mut tags: Vec<_> = self.tags.iter().collect();
+            tags.sort_by_key(|(path, _)| *path);
+            for (path, tag) in tags {
+                if let Some(path) = storable(path) {
+                    text.push_str(&format!("tag={}|{}\n", tag.name(), path));
                 }
             }
-            Message::DriveSelected(drive_path) => {
-                self.path = drive_path;
-                self.show_drives = false;
-                self.list_files()
+            // Written back as they were read, so saving another setting keeps them
+            for action in &self.custom_actions {
+                text.push_str(&format!("custom_action={}\n", action.to_setting()));
             }
+
+            let temp = path.with_extension("tmp");
+            let mut file = fs::File::create(&temp)?;
+            file.write_all(text.as_bytes())?;
+            file.sync_all()?;
+            fs::rename(&temp, &path)
         }
     }
 
-    fn view(&mut self) -> Element<Message> {
-        // Main column with spacing and padding
-        let mut column = Column::new().spacing(10).padding(10);
+    #[cfg(test)]
+    mod tests {
+        use super::*;
 
-        // 1. Show current directory at top
-        column = column.push(
-            Text::new(format!("Directory: {}", self.path.display()))
-            .size(16)
-        );
+        #[test]
+        fn strict_parse_rejects_what_lenient_parse_skips() {
+            let text = "theme=dark\nclick_mode=tripple\ndouble_click_ms=300\n";
+            let config = Config::parse(text, false).unwrap();
+            assert_eq!(config.theme, Theme::Dark);
+            assert_eq!(config.click_mode, ClickMode::DoubleClick);
+            assert_eq!(config.double_click_ms, 300);
+            let err = Config::parse(text, true).unwrap_err();
+            assert!(err.starts_with("line 2 "), "{}", err);
+        }
 
-        // Top buttons row
-        let mut top_row = Row::new().spacing(10);
-        top_row = top_row.push(
-            Button::new(&mut self.refresh_button, Text::new("Refresh"))
-                .on_press(Message::Refresh)
-                .padding(5),
-        );
-        top_row = top_row.push(
-            Button::new(&mut self.up_button, Text::new("Go Up"))
-                .on_press(Message::GoUp)
-                .padding(5),
-        );
-        
-        // Drive button (Windows only)
-        if cfg!(windows) {
-            top_row = top_row.push(
-                Button::new(&mut self.drive_button, Text::new("Drives"))
-                    .on_press(Message::DriveSelected(PathBuf::from("C:\\")))  // or any default drive
-                    .padding(5),
-            );
+        #[test]
+        fn parse_reads_what_save_writes() {
+            let text = "last_dir=/tmp\nbookmark=/a\nbookmark=/b\nrecent_destination=/c\n\
+                        theme=light\n\
+                        click_mode=single\ndouble_click_ms=700\nicon_set=text\n\
+                        symlink_target_style=absolute\nconfirm_discard=false\n\
+                        custom_action=Zip it|dir|zip -r {path}.zip {path}\n\
+                        tag=green|/a/b|c\n";
+            let config = Config::parse(text, true).unwrap();
+            assert_eq!(config.last_dir, Some(PathBuf::from("/tmp")));
+            assert_eq!(config.bookmarks, [PathBuf::from("/a"), PathBuf::from("/b")]);
+            assert_eq!(config.recent_destinations, [PathBuf::from("/c")]);
+            assert_eq!(config.click_mode, ClickMode::SingleClick);
+            assert_eq!(config.double_click_ms, 700);
+            assert_eq!(config.icon_set, IconSet::Text);
+            assert_eq!(config.link_target_style, LinkTargetStyle::Absolute);
+            assert!(!config.confirm_discard);
+            let [action] = config.custom_actions.as_slice() else {
+                panic!("expected one custom action, got {:?}", config.custom_actions);
+            };
+            assert_eq!(action.to_setting(), "Zip it|dir|zip -r {path}.zip {path}");
+            assert_eq!(config.tags, HashMap::from([(PathBuf::from("/a/b|c"), TagColor::Green)]));
+            assert!(Config::parse("tag=teal|/a\n", true).is_err());
         }
-        
-        column = column.push(top_row);
+    }
+}
 
-        // Drive selection (if shown)
-        if self.show_drives {
-            self.drive_buttons
-                .resize_with(self.drives.len(), button::State::new);
+/// Polling watch on the current folder. A notification crate isn't available to this
+/// build, so the folder's entries are compared every `POLL_INTERVAL` instead.
+mod watch {
+    use super::{EntryMeta, Message};
+    use iced::futures::{channel::mpsc, stream::BoxStream, StreamExt};
+    use std::{
+        collections::HashMap,
+        ffi::OsString,
+        fs,
+        hash::{Hash, Hasher},
+        path::{Path, PathBuf},
+        thread,
+        time::{Duration, SystemTime},
+    };
 
-            column = column.push(Text::new("Select Drive:").size(14));
-            
-            let mut drives_row = Row::new().spacing(5);
-            let drive_buttons = self.drive_buttons.iter_mut();
-            
-            for (drive, btn_state) in self.drives.iter().zip(drive_buttons) {
-                let drive_name = drive.display().to_string();
-                let is_current = self.path.starts_with(drive);
-                
-                let button = Button::new(
-                    btn_state, 
-                    Text::new(if is_current {
-                        format!("[{}]", drive_name)
-                    } else {
-                        drive_name.clone()
-                    })
-                )
-                .on_press(Message::DriveSelected(drive.clone()))
-                .padding(5);
-                
-                drives_row = drives_row.push(button);
-            }
-            
-            column = column.push(                        
-                        Scrollable::new(&mut self.drives_scroll)
-                            .push(drives_row)
-                            .height(Length::Units(40))
-                            .width(Length::Fill)
-                    );
-                    
-                    column = column.push(Space::with_height(Length::Units(10)));
-                }
+    const POLL_INTERVAL: Duration = Duration::from_secs(1);
 
-                // Files list with proper spacing
-                let mut files_column = Column::new().spacing(5);
-                self.file_buttons
-                    .resize_with(self.files.len(), button::State::new);
+    // More changed entries than this are cheaper to pick up with a full re-list
+    const MAX_CHANGES: usize = 256;
 
-                for (file, btn_state) in self.files.iter().zip(self.file_buttons.iter_mut()) {
-                    let display_name = if file == &PathBuf::from("..") {
-                        ".. (parent)".to_string()
-                    } else {
-                        file.file_name()
-                            .and_then(|n| n.to_str())
-                            .unwrap_or("Unknown")
-                            .to_string()
-                    };
+    /// Entries that appeared, disappeared or changed since the last report. A rename
+    /// shows up as one entry removed and another added.
+    #[derive(Debug, Clone, Default)]
+    pub struct Changes {
+        pub added: Vec<(PathBuf, EntryMeta)>,
+        pub removed: Vec<PathBuf>,
+        pub modified: Vec<(PathBuf, EntryMeta)>,
+    }
 
-                    // 2. Show files/directories with icons
-                    let prefix = if file.is_dir() { 
-                        Text::new("📁 ")  // Folder icon
-                    } else {
-                        Text::new("📄 ")  // File icon
-                    };
+    /// Subscription sending `Message::DirectoryChanged` when entries in the folder are
+    /// created, deleted, renamed or modified. A burst of changes (say, a build writing
+    /// many files) is reported once, after a poll finds the folder unchanged again.
+    /// The report carries what changed, with fresh metadata, or `None` when the folder
+    /// should be re-listed in full instead.
+    pub struct Watch(pub PathBuf);
 
-                    let full_text = Row::new()
-                        .push(prefix)
-                        .push(Text::new(display_name));
+    impl<H: Hasher, I> iced_native::subscription::Recipe<H, I> for Watch {
+        type Output = Message;
 
-                    // 3. Navigate into directories by clicking
-                    let button = Button::new(btn_state, full_text)
-                        .on_press(Message::FileClicked(file.clone()))
-                        .padding(5);
+        fn hash(&self, state: &mut H) {
+            std::any::TypeId::of::<Self>().hash(state);
+            self.0.hash(state);
+        }
 
-                    files_column = files_column.push(button);
+        fn stream(self: Box<Self>, _input: BoxStream<'static, I>) -> BoxStream<'static, Message> {
+            let (sender, receiver) = mpsc::unbounded();
+            let dir = self.0;
+            thread::spawn(move || {
+                // What was last reported, and what the last poll found
+                let mut reported = snapshot(&dir);
+                let mut last = reported.clone();
+                let mut changed = false;
+                // Ends once iced drops the subscription, i.e. we navigated elsewhere
+                while !sender.is_closed() {
+                    thread::sleep(POLL_INTERVAL);
+                    let current = snapshot(&dir);
+                    if current != last {
+                        last = current;
+                        changed = true;
+                    } else if changed {
+                        changed = false;
+                        let changes = diff(&dir, reported.as_ref(), current.as_ref());
+                        reported = current;
+                        let message = Message::DirectoryChanged(dir.clone(), changes);
+                        if sender.unbounded_send(message).is_err() {
+                            return;
+                        }
+                    }
                 }
+            });
+            receiver.boxed()
+        }
+    }
 
-                column = column.push(
-                    Scrollable::new(&mut self.scroll)
-                        .push(files_column)
-                        .height(Length::Fill)
-                );
+    /// An entry's modification time and length, enough to notice it was written to.
+    type Stamp = (Option<SystemTime>, u64);
 
-                column.into()
+    /// The folder's entries by name; `None` while it can't be read.
+    fn snapshot(dir: &Path) -> Option<HashMap<OsString, Stamp>> {
+        let entries = fs::read_dir(super::long_path(dir)).ok()?;
+        Some(
+            entries
+                .flatten()
+                .map(|entry| {
+                    // The entry itself, not what a link points at
+                    let stamp = entry
+                        .metadata()
+                        .map_or((None, 0), |meta| (meta.modified().ok(), meta.len()));
+                    (entry.file_name(), stamp)
+                })
+                .collect(),
+        )
+    }
+
+    /// What turned `before` into `after`, metadata included. `None` when either
+    /// snapshot is missing or so much changed that a full re-list is simpler.
+    fn diff(
+        dir: &Path,
+        before: Option<&HashMap<OsString, Stamp>>,
+        after: Option<&HashMap<OsString, Stamp>>,
+    ) -> Option<Changes> {
+        let (before, after) = (before?, after?);
+        let mut changes = Changes::default();
+        let mut count = 0;
+        for (name, stamp) in after {
+            let slot = match before.get(name) {
+                None => &mut changes.added,
+                Some(old) if old != stamp => &mut changes.modified,
+                Some(_) => continue,
+            };
+            count += 1;
+            if count > MAX_CHANGES {
+                return None;
+            }
+            let path = dir.join(name);
+            let meta = EntryMeta::read(&path);
+            slot.push((path, meta));
+        }
+        for name in before.keys().filter(|name| !after.contains_key(*name)) {
+            count += 1;
+            if count > MAX_CHANGES {
+                return None;
             }
+            changes.removed.push(dir.join(name));
         }
+        Some(changes)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn diff_reports_added_removed_and_modified_entries() {
+            let dir = crate::fs_ops::tests::temp_dir("watch-diff");
+            fs::write(dir.join("kept.txt"), "x").unwrap();
+            fs::write(dir.join("changed.txt"), "x").unwrap();
+            fs::write(dir.join("gone.txt"), "x").unwrap();
+            let before = snapshot(&dir);
+
+            fs::write(dir.join("changed.txt"), "longer").unwrap();
+            fs::remove_file(dir.join("gone.txt")).unwrap();
+            fs::write(dir.join("new.txt"), "x").unwrap();
+            let changes = diff(&dir, before.as_ref(), snapshot(&dir).as_ref()).unwrap();
+
+            let paths = |entries: &[(PathBuf, EntryMeta)]| -> Vec<PathBuf> {
+                entries.iter().map(|(path, _)| path.clone()).collect()
+            };
+            assert_eq!(paths(&changes.added), [dir.join("new.txt")]);
+            assert_eq!(paths(&changes.modified), [dir.join("changed.txt")]);
+            assert_eq!(changes.removed, [dir.join("gone.txt")]);
+            assert!(diff(&dir, None, before.as_ref()).is_none());
+            fs::remove_dir_all(&dir).unwrap();
+        }
+    }
+}
 
 fn main() -> iced::Result {
-    FileExplorer::run(Settings::default())
+    // An optional directory to open, resolved against where we were launched from
+    let start_path = env::args_os().nth(1).map(|arg| {
+        let path = PathBuf::from(arg);
+        match env::current_dir() {
+            Ok(cwd) if path.is_relative() => cwd.join(path),
+            _ => path,
+        }
+    });
+
+    #[cfg(unix)]
+    let ipc_listener = match ipc::claim(start_path.as_deref()) {
+        Ok(ipc::Instance::Primary(listener)) => Some(std::sync::Arc::new(listener)),
+        Ok(ipc::Instance::Forwarded) => return Ok(()),
+        Err(_) => None,
+    };
+
+    let settings = Settings::with_flags(Flags {
+        start_path,
+        #[cfg(unix)]
+        ipc_listener,
+    });
+    // Closing may have to wait for the user to confirm discarding a dialog's input
+    FileExplorer::run(Settings { exit_on_close_request: false, ..settings })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{cell::RefCell, rc::Rc};
+
+    /// Records what it was asked to launch, as "open <path>" and the like, instead of
+    /// starting anything.
+    #[derive(Default)]
+    struct RecordingLauncher {
+        calls: Rc<RefCell<Vec<String>>>,
+    }
+
+    impl Launcher for RecordingLauncher {
+        fn open(&self, path: &Path) -> io::Result<()> {
+            self.calls.borrow_mut().push(format!("open {}", display_path(path)));
+            Ok(())
+        }
+
+        fn reveal(&self, path: &Path) -> io::Result<()> {
+            self.calls.borrow_mut().push(format!("reveal {}", display_path(path)));
+            Ok(())
+        }
+
+        fn compare(&self, a: &Path, b: &Path) -> io::Result<()> {
+            let call = format!("compare {} {}", display_path(a), display_path(b));
+            self.calls.borrow_mut().push(call);
+            Ok(())
+        }
+    }
+
+    /// An explorer with one tab on `dir`, without reading anything from disk.
+    fn explorer_at(dir: &Path) -> FileExplorer {
+        FileExplorer {
+            tabs: vec![Tab { path: dir.to_path_buf(), ..Tab::default() }],
+            ..FileExplorer::default()
+        }
+    }
+
+    #[test]
+    fn address_bar_file_path_opens_the_parent_with_the_file_selected() {
+        let dir = fs::canonicalize(fs_ops::tests::temp_dir("address-file")).unwrap();
+        let file = dir.join("notes.txt");
+        fs::write(&file, "x").unwrap();
+        let mut explorer = explorer_at(Path::new("/"));
+        explorer.address_value = display_path(&file);
+
+        let _ = explorer.submit_address();
+        assert_eq!(explorer.address_error, None);
+        assert_eq!(explorer.tab().path, long_path(&dir));
+        assert_eq!(explorer.tab().select_on_load, Some(long_path(&file)));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn leaving_with_an_unfinished_rename_waits_for_discard() {
+        let dir = fs::canonicalize(fs_ops::tests::temp_dir("discard-guard")).unwrap();
+        let (file, other) = (dir.join("notes.txt"), dir.join("other"));
+        fs::write(&file, "x").unwrap();
+        fs::create_dir(&other).unwrap();
+        let mut explorer = explorer_at(&dir);
+        explorer.renaming = Some(file.clone());
+        explorer.rename_value = "notes.txt".to_string();
+        // Nothing typed yet, so there's nothing to lose
+        assert!(explorer.unsaved_input().is_none());
+
+        explorer.rename_value = "renamed.txt".to_string();
+        let _ = explorer.navigate_to(other.clone());
+        assert_eq!(explorer.tab().path, dir);
+        assert_eq!(explorer.renaming, Some(file.clone()));
+        assert!(explorer.pending_leave.is_some());
+
+        let _ = explorer.discard_and_leave();
+        assert_eq!(explorer.tab().path, long_path(&other));
+        assert_eq!(explorer.renaming, None);
+        assert!(explorer.pending_leave.is_none());
+
+        // With the setting off, leaving drops the input straight away
+        explorer.config.confirm_discard = false;
+        explorer.renaming = Some(file);
+        let _ = explorer.navigate_to(dir.clone());
+        assert_eq!(explorer.tab().path, long_path(&dir));
+        assert_eq!(explorer.renaming, None);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn link_target_text_marks_a_target_that_cannot_be_resolved() {
+        let (raw, resolved) = (Path::new("../lib"), Path::new("/usr/lib"));
+        assert_eq!(link_target_text(raw, Some(resolved), LinkTargetStyle::Raw), "../lib");
+        assert_eq!(link_target_text(raw, Some(resolved), LinkTargetStyle::Absolute), "/usr/lib");
+        assert_eq!(link_target_text(raw, None, LinkTargetStyle::Absolute), "../lib (unresolved)");
+    }
+
+    #[test]
+    fn selection_breakdown_totals_files_and_measured_folders() {
+        let dir = Path::new("/data");
+        let mut explorer = explorer_at(dir);
+        let (small, big, measured, unmeasured) =
+            (dir.join("a.txt"), dir.join("b.iso"), dir.join("docs"), dir.join("music"));
+        let folder = EntryMeta { is_dir: true, ..EntryMeta::UNKNOWN };
+        let tab = explorer.tab_mut();
+        tab.entry_meta.insert(small.clone(), EntryMeta { size: Some(10), ..EntryMeta::UNKNOWN });
+        tab.entry_meta.insert(big.clone(), EntryMeta { size: Some(5000), ..EntryMeta::UNKNOWN });
+        tab.entry_meta.insert(measured.clone(), folder.clone());
+        tab.entry_meta.insert(unmeasured.clone(), folder);
+        tab.selected = [&small, &big, &measured, &unmeasured].into_iter().cloned().collect();
+        explorer.dir_size_cache.insert(
+            measured,
+            DirSize { size: 300, computed_at: SystemTime::now(), dir_mtime: None, stale: false },
+        );
+
+        let breakdown = explorer.selection_breakdown().unwrap();
+        assert_eq!(breakdown.count, 4);
+        assert_eq!(breakdown.bytes, 5310);
+        assert_eq!(breakdown.largest, Some((big, 5000)));
+        assert_eq!(breakdown.unmeasured, 1);
+        assert!(breakdown.report().contains("Not yet measured: 1 folders"));
+
+        explorer.tab_mut().selected = [small].into_iter().collect();
+        assert!(explorer.selection_breakdown().is_none());
+    }
+
+    #[test]
+    fn validate_entry_name_trims_and_refuses_names_outside_the_folder() {
+        assert_eq!(FileExplorer::validate_entry_name("Photos  "), Ok("Photos"));
+        assert_eq!(FileExplorer::validate_entry_name(" leading"), Ok(" leading"));
+        assert!(FileExplorer::validate_entry_name("   ").is_err());
+        assert!(FileExplorer::validate_entry_name("a/b").is_err());
+        assert!(FileExplorer::validate_entry_name(".").is_err());
+        assert!(FileExplorer::validate_entry_name("..").is_err());
+    }
+
+    #[test]
+    fn open_selected_hands_a_file_to_the_launcher() {
+        let dir = fs_ops::tests::temp_dir("open-selected");
+        let file = dir.join("notes.txt");
+        fs::write(&file, "x").unwrap();
+        let launcher = RecordingLauncher::default();
+        let calls = launcher.calls.clone();
+        let mut explorer = explorer_at(&dir);
+        explorer.launcher = Box::new(launcher);
+        let tab = explorer.tab_mut();
+        tab.files = vec![file.clone()];
+        tab.entry_meta.insert(file.clone(), EntryMeta::read(&file));
+        tab.selected_index = Some(0);
+
+        let _ = explorer.open_selected();
+        assert_eq!(explorer.error_message, None);
+        assert_eq!(*calls.borrow(), [format!("open {}", display_path(&file))]);
+        // A folder opens in the app instead
+        explorer.tab_mut().files = vec![PathBuf::from("..")];
+        let _ = explorer.open_selected();
+        assert_eq!(calls.borrow().len(), 1);
+        assert_eq!(explorer.tab().path, long_path(dir.parent().unwrap()));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn tab_shortcuts_are_left_to_a_focused_text_input() {
+        // Cmd on macOS, Ctrl elsewhere
+        let mac = cfg!(target_os = "macos");
+        let ctrl = keyboard::Modifiers { control: !mac, logo: mac, ..Default::default() };
+        let ctrl_shift = keyboard::Modifiers { shift: true, ..ctrl };
+        let press = |modifiers| {
+            Event::Keyboard(keyboard::Event::KeyPressed {
+                key_code: keyboard::KeyCode::T,
+                modifiers,
+            })
+        };
+        let shortcut = |modifiers, status| shortcut_for(press(modifiers), status);
+        let open_selected = shortcut(ctrl, event::Status::Ignored);
+        assert!(matches!(open_selected, Some(Message::OpenSelectedInNewTab)));
+        assert!(matches!(shortcut(ctrl_shift, event::Status::Ignored), Some(Message::NewTab)));
+        assert!(shortcut(ctrl, event::Status::Captured).is_none());
+        assert!(shortcut(ctrl_shift, event::Status::Captured).is_none());
+    }
+
+    /// A listing of `count` entries named "entry0", "entry1" and so on, every tenth a
+    /// folder, in reverse order so the sort has work to do.
+    fn synthetic_listing(count: usize) -> (Vec<PathBuf>, HashMap<PathBuf, EntryMeta>) {
+        let mut meta = HashMap::new();
+        let entries: Vec<PathBuf> = (0..count)
+            .map(|i| {
+                let path = PathBuf::from(format!("/dir/entry{}", i));
+                let is_dir = i % 10 == 0;
+                let entry_meta = EntryMeta { is_dir, size: Some(i as u64), ..EntryMeta::UNKNOWN };
+                meta.insert(path.clone(), entry_meta);
+                path
+            })
+            .rev()
+            .collect();
+        (entries, meta)
+    }
+
+    /// The rows shown as the app does it: the listing is sorted once, and the filter
+    /// picks rows out of it.
+    fn rows_sorted_first(sorted: &[PathBuf], filter: &NameFilter) -> Vec<PathBuf> {
+        let parent = std::iter::once(PathBuf::from(".."));
+        parent.chain(sorted.iter().filter(|file| filter.matches(file)).cloned()).collect()
+    }
+
+    /// The same rows worked out the other way round: the filter first, then a sort of
+    /// only what matched.
+    fn rows_filtered_first(
+        entries: &[PathBuf],
+        meta: &HashMap<PathBuf, EntryMeta>,
+        filter: &NameFilter,
+    ) -> Vec<PathBuf> {
+        let mut matched: Vec<_> =
+            entries.iter().filter(|file| filter.matches(file)).cloned().collect();
+        sort_entries(&mut matched, meta, SortKey::Name, true);
+        std::iter::once(PathBuf::from("..")).chain(matched).collect()
+    }
+
+    #[test]
+    fn filtering_before_or_after_the_sort_shows_the_same_rows() {
+        let (entries, meta) = synthetic_listing(500);
+        let mut sorted = entries.clone();
+        sort_entries(&mut sorted, &meta, SortKey::Name, true);
+        let mut filter = NameFilter::default();
+        for text in ["", "1", "12", "entry4?", "*0"] {
+            let glob = text.contains(['?', '*']);
+            let mode = if glob { FilterMode::Glob } else { FilterMode::Contains };
+            filter.set(text.to_string(), mode);
+            let shown = rows_sorted_first(&sorted, &filter);
+            assert_eq!(shown, rows_filtered_first(&entries, &meta, &filter), "{:?}", text);
+            // `..` stays on top and folders before files either way
+            assert_eq!(shown[0], Path::new(".."));
+            let dirs = shown[1..].iter().map(|file| meta[file].is_dir);
+            assert!(dirs.collect::<Vec<_>>().windows(2).all(|pair| pair[0] >= pair[1]));
+        }
+    }
+
+    /// Times the two orders for typing a filter into a big folder. Run it with
+    /// `cargo test --release -- --ignored --nocapture filter_order`.
+    #[test]
+    #[ignore]
+    fn filter_order_benchmark() {
+        const KEYSTROKES: [&str; 4] = ["1", "12", "123", "1234"];
+        for count in [10_000, 100_000, 1_000_000] {
+            let (entries, meta) = synthetic_listing(count);
+            let mut filter = NameFilter::default();
+
+            let started = Instant::now();
+            let mut sorted = entries.clone();
+            sort_entries(&mut sorted, &meta, SortKey::Name, true);
+            let sort_once = started.elapsed();
+            for text in KEYSTROKES {
+                filter.set(text.to_string(), FilterMode::Contains);
+                std::hint::black_box(rows_sorted_first(&sorted, &filter));
+            }
+            let sorted_first = started.elapsed();
+
+            let started = Instant::now();
+            for text in KEYSTROKES {
+                filter.set(text.to_string(), FilterMode::Contains);
+                std::hint::black_box(rows_filtered_first(&entries, &meta, &filter));
+            }
+            let filtered_first = started.elapsed();
+            println!(
+                "{} entries: sort once {:?}, then typing: sorted first {:?}, filtered first {:?}",
+                count,
+                sort_once,
+                sorted_first - sort_once,
+                filtered_first
+            );
+        }
+    }
+
+    #[test]
+    fn file_icon_goes_by_folder_then_extension() {
+        let cases = [
+            ("projects", true, "dir"),
+            ("archive.zip", true, "dir"),  // A folder with a file-like name
+            ("linked-folder", true, "dir"),  // A link to a folder, as the listing reports it
+            ("song-link.mp3", false, "aud"),  // A link to a file goes by its own extension
+            ("photo.PNG", false, "img"),
+            ("backup.tar.gz", false, "arc"),
+            ("clip.mkv", false, "vid"),
+            ("main.rs", false, "src"),
+            ("notes.md", false, "txt"),
+            ("manual.pdf", false, "pdf"),
+            ("setup.exe", false, "exe"),
+            ("data.unknownext", false, "file"),
+            ("Makefile", false, "file"),
+            (".bashrc", false, "file"),
+        ];
+        for (name, is_dir, label) in cases {
+            assert_eq!(file_icon(Path::new(name), is_dir).1, label, "{}", name);
+        }
+    }
+
+    #[test]
+    fn click_tracker_uses_the_threshold_it_is_given() {
+        let (a, b) = (Path::new("/a"), Path::new("/b"));
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let threshold = Duration::from_millis(300);
+        let mut clicks = ClickTracker::default();
+
+        assert!(!clicks.click(a, at(0), threshold));
+        assert!(clicks.click(a, at(250), threshold));
+        // A third click starts a new pair
+        assert!(!clicks.click(a, at(260), threshold));
+        // Too slow for 300ms, though it would do for the default 500ms
+        assert!(!clicks.click(a, at(660), threshold));
+        assert!(clicks.click(a, at(900), Duration::from_millis(500)));
+        // A click on another entry never completes the pair
+        assert!(!clicks.click(a, at(1000), threshold));
+        assert!(!clicks.click(b, at(1010), threshold));
+        clicks.reset();
+        assert!(!clicks.click(b, at(1020), threshold));
+    }
 }